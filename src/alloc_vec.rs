@@ -0,0 +1,265 @@
+use std::alloc::Layout;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use crate::NodeAllocator;
+
+/// `Vec`, with memory from [NodeAllocator].
+pub(crate) struct AllocVec<T, A: NodeAllocator> {
+    ptr: NonNull<T>,
+    len: usize,
+    capacity: usize,
+    allocator: A,
+    phantom: PhantomData<T>,
+}
+
+unsafe impl<T: Send, A: NodeAllocator + Send> Send for AllocVec<T, A> {}
+unsafe impl<T: Sync, A: NodeAllocator + Sync> Sync for AllocVec<T, A> {}
+
+impl<T, A: NodeAllocator> AllocVec<T, A> {
+    const MIN_CAPACITY: usize = 4;
+    const IS_ZST: bool = size_of::<T>() == 0;
+
+    #[inline]
+    pub fn new_in(allocator: A) -> Self {
+        let capacity = if Self::IS_ZST { usize::MAX } else { 0 };
+        Self{ ptr: NonNull::dangling(), len: 0, capacity, allocator, phantom: PhantomData }
+    }
+
+    #[inline]
+    pub fn with_capacity_in(capacity: usize, allocator: A) -> Self {
+        let mut this = Self::new_in(allocator);
+        this.reserve(capacity);
+        this
+    }
+
+    /// `AllocVec` with uninitialized first element.
+    ///
+    /// Containers use it as a placeholder for non-existent element.
+    ///
+    /// # Safety
+    ///
+    /// First element must be initialized before use, or never read/dropped.
+    #[inline]
+    pub unsafe fn with_placeholder_in(capacity: usize, allocator: A) -> Self {
+        let mut this = Self::with_capacity_in(capacity.max(1), allocator);
+        this.set_len(1);
+        this
+    }
+
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
+
+    #[inline]
+    fn layout(capacity: usize) -> Layout {
+        Layout::array::<T>(capacity).expect("capacity overflow")
+    }
+
+    /// Change capacity to non-zero `new_capacity`, that fits `len`.
+    #[inline(never)]
+    fn set_capacity(&mut self, new_capacity: usize) {
+        debug_assert!(!Self::IS_ZST && new_capacity >= self.len && new_capacity != 0);
+        let new_layout = Self::layout(new_capacity);
+        let ptr = if self.capacity == 0 {
+            self.allocator.allocate(new_layout)
+        } else {
+            unsafe{
+                self.allocator.reallocate(
+                    self.ptr.cast(),
+                    Self::layout(self.capacity),
+                    new_layout.size()
+                )
+            }
+        };
+        self.ptr = ptr.cast();
+        self.capacity = new_capacity;
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn grow(&mut self, min_capacity: usize) {
+        if Self::IS_ZST {
+            panic!("capacity overflow");
+        }
+        let new_capacity = (self.capacity * 2).max(min_capacity).max(Self::MIN_CAPACITY);
+        self.set_capacity(new_capacity);
+    }
+
+    /// Reserve space for at least `additional` more elements.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        let min_capacity = self.len.checked_add(additional).expect("capacity overflow");
+        if min_capacity > self.capacity {
+            self.grow(min_capacity);
+        }
+    }
+
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        if Self::IS_ZST || self.capacity == self.len {
+            return;
+        }
+        if self.len == 0 {
+            unsafe{ self.allocator.deallocate(self.ptr.cast(), Self::layout(self.capacity)); }
+            self.ptr = NonNull::dangling();
+            self.capacity = 0;
+        } else {
+            self.set_capacity(self.len);
+        }
+    }
+
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        if self.len == self.capacity {
+            self.grow(self.len + 1);
+        }
+        unsafe{ self.ptr.as_ptr().add(self.len).write(value); }
+        self.len += 1;
+    }
+
+    #[inline]
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(unsafe{ self.ptr.as_ptr().add(self.len).read() })
+    }
+
+    /// Same as [Vec::swap_remove].
+    #[inline]
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "swap_remove index out of bounds");
+        unsafe{
+            let value = self.ptr.as_ptr().add(index).read();
+            self.len -= 1;
+            let base = self.ptr.as_ptr();
+            ptr::copy(base.add(self.len), base.add(index), 1);
+            value
+        }
+    }
+
+    #[inline]
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        unsafe{
+            let tail = ptr::slice_from_raw_parts_mut(self.ptr.as_ptr().add(len), self.len - len);
+            // Shrink first - if some drop panics, the rest is leaked. Same as Vec.
+            self.len = len;
+            ptr::drop_in_place(tail);
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// # Safety
+    ///
+    /// Same as [Vec::set_len].
+    #[inline]
+    pub unsafe fn set_len(&mut self, len: usize) {
+        debug_assert!(len <= self.capacity);
+        self.len = len;
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr.as_ptr()
+    }
+
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe{ slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe{ slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    #[inline]
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone
+    {
+        self.reserve(other.len());
+        for value in other {
+            self.push(value.clone());
+        }
+    }
+}
+
+impl<T, A: NodeAllocator> Deref for AllocVec<T, A> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, A: NodeAllocator> DerefMut for AllocVec<T, A> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: Clone, A: NodeAllocator + Clone> Clone for AllocVec<T, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        let mut out = Self::with_capacity_in(self.len, self.allocator.clone());
+        out.extend_from_slice(self.as_slice());
+        out
+    }
+}
+
+impl<T, A: NodeAllocator> AllocVec<T, A> {
+    #[inline]
+    unsafe fn drop_impl(&mut self) {
+        ptr::drop_in_place(self.as_mut_slice());
+        if !Self::IS_ZST && self.capacity != 0 {
+            self.allocator.deallocate(self.ptr.cast(), Self::layout(self.capacity));
+        }
+    }
+}
+
+#[cfg(feature = "may_dangle")]
+unsafe impl<#[may_dangle] T, A: NodeAllocator> Drop for AllocVec<T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe{ self.drop_impl(); }
+    }
+}
+
+#[cfg(not(feature = "may_dangle"))]
+impl<T, A: NodeAllocator> Drop for AllocVec<T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe{ self.drop_impl(); }
+    }
+}
@@ -1,5 +1,5 @@
 use std::mem;
-use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, ControlFlow};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, ControlFlow, Not};
 use crate::bit_queue::{ArrayBitQueue, BitQueue, EmptyBitQueue, PrimitiveBitQueue};
 use crate::bit_utils;
 use crate::utils::Array;
@@ -12,6 +12,8 @@ pub trait BitBlock
     + BitOr<Output = Self>
     + BitOrAssign<Self>
     + for<'a> BitOrAssign<&'a Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
     + Sized + Clone + 'static
 {
     /// Size in bits
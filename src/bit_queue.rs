@@ -24,6 +24,28 @@ fn trailing_zeroes<P: Primitive>(bit_block_iter: &OneBitsIter<P>) -> usize{
     block.trailing_zeros() as usize
 }
 
+/// Index of the last one bit. `None` if there is no bits.
+#[inline]
+fn last_one_bit<P: Primitive>(bit_block_iter: &OneBitsIter<P>) -> Option<usize>{
+    let block: &P = unsafe{
+        mem::transmute(bit_block_iter)
+    };
+    if block.is_zero() {
+        None
+    } else {
+        Some(size_of::<P>() * 8 - 1 - block.leading_zeros() as usize)
+    }
+}
+
+/// Zero all bits after `n`. `n` must be within `P`.
+#[inline]
+fn zero_after<P: Primitive>(bit_block_iter: &mut OneBitsIter<P>, n: usize){
+    let block: &mut P = unsafe{
+        mem::transmute(bit_block_iter)
+    };
+    *block &= P::MAX >> (size_of::<P>() * 8 - 1 - n);
+}
+
 /*#[inline]
 fn is_empty<P: Primitive>(bit_block_iter: &OneBitsIter<P>) -> bool{
     let block: &P = unsafe{
@@ -35,9 +57,12 @@ fn is_empty<P: Primitive>(bit_block_iter: &OneBitsIter<P>) -> bool{
 /// Queue of 1 bits.
 /// 
 /// Pop first set bit on iteration. "Consumed" bit replaced with zero.
+/// [next_back()] pops last set bit.
 /// 
 /// Think of it as an iterator that owns data.
-pub trait BitQueue: Iterator<Item = usize> + Clone{
+/// 
+/// [next_back()]: DoubleEndedIterator::next_back
+pub trait BitQueue: DoubleEndedIterator<Item = usize> + Clone{
     /// All bits 0. Iterator returns None.
     fn empty() -> Self;
 
@@ -48,9 +73,17 @@ pub trait BitQueue: Iterator<Item = usize> + Clone{
     /// 
     /// If n >= BitQueue capacity - make it empty.
     fn trim_to(&mut self, n: usize);
+    
+    /// Remove all bits after n. (Set 0)
+    /// 
+    /// If n >= BitQueue capacity - do nothing.
+    fn trim_after(&mut self, n: usize);
 
     /// Current index. Equals capacity - if iteration finished.
     fn current(&self) -> usize;
+    
+    /// Current index from the back. Equals capacity - if iteration finished.
+    fn current_back(&self) -> usize;
 
     fn traverse<F>(self, f: F) -> ControlFlow<()>
     where
@@ -72,6 +105,13 @@ impl Iterator for EmptyBitQueue {
     }
 }
 
+impl DoubleEndedIterator for EmptyBitQueue {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        None
+    }
+}
+
 impl BitQueue for EmptyBitQueue{
     fn empty() -> Self {
         Self
@@ -82,10 +122,16 @@ impl BitQueue for EmptyBitQueue{
     }
 
     fn trim_to(&mut self, _n: usize) {}
+    
+    fn trim_after(&mut self, _n: usize) {}
 
     fn current(&self) -> usize {
         0
     }
+    
+    fn current_back(&self) -> usize {
+        0
+    }
 
     fn traverse<F>(self, _f: F) -> ControlFlow<()> 
     where 
@@ -132,11 +178,23 @@ where
         let mask = saturating_shl(P::MAX, n);
         *block &= mask;
     }
+    
+    #[inline]
+    fn trim_after(&mut self, n: usize) {
+        if n < size_of::<P>() * 8 {
+            zero_after(&mut self.bit_block_iter, n);
+        }
+    }
 
     #[inline]
     fn current(&self) -> usize {
         trailing_zeroes(&self.bit_block_iter)
     }
+    
+    #[inline]
+    fn current_back(&self) -> usize {
+        last_one_bit(&self.bit_block_iter).unwrap_or(size_of::<P>() * 8)
+    }
 
     #[inline]
     fn traverse<F>(self, f: F) -> ControlFlow<()> where F: FnMut(usize) -> ControlFlow<()> {
@@ -164,6 +222,16 @@ where
     }
 }
 
+impl<P> DoubleEndedIterator for PrimitiveBitQueue<P>
+where
+    P: Primitive
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.bit_block_iter.next_back()
+    }
+}
+
 /// [BitQueue] for array of [Primitive]s.
 #[derive(Clone)]
 pub struct ArrayBitQueue<P, const N: usize>{
@@ -264,11 +332,52 @@ where
         }
     }
 
+    #[inline]
+    fn trim_after(&mut self, n: usize) {
+        let element_index = n / (size_of::<P>() * 8); // compile-time math optimization
+        if element_index >= N {
+            return;
+        }
+        
+        // everything left is after n?
+        if element_index < self.bit_block_index {
+            self.bit_block_iters[0] = one_bits_iter(P::ZERO);
+            self.bit_block_index = N-1;
+            return;
+        }
+        
+        // Blocks after active one are in place.
+        for block_iter in &mut self.bit_block_iters[element_index+1..] {
+            *block_iter = one_bits_iter(P::ZERO);
+        }
+        
+        let bit_index = n % (size_of::<P>() * 8); // compile-time math optimization
+        let block_iter = if element_index == self.bit_block_index {
+            &mut self.bit_block_iters[0]
+        } else {
+            &mut self.bit_block_iters[element_index]
+        };
+        zero_after(block_iter, bit_index);
+    }
+
     #[inline]
     fn current(&self) -> usize {
         let active_block_iter = &self.bit_block_iters[0];
         self.bit_block_index * size_of::<P>() * 8 + trailing_zeroes(active_block_iter)
     }
+    
+    #[inline]
+    fn current_back(&self) -> usize {
+        for element_index in (self.bit_block_index+1..N).rev() {
+            if let Some(index) = last_one_bit(&self.bit_block_iters[element_index]) {
+                return element_index * size_of::<P>() * 8 + index;
+            }
+        }
+        if let Some(index) = last_one_bit(&self.bit_block_iters[0]) {
+            return self.bit_block_index * size_of::<P>() * 8 + index;
+        }
+        N * size_of::<P>() * 8
+    }
 
     #[inline]
     fn traverse<F>(mut self, mut f: F) -> ControlFlow<()>
@@ -335,4 +444,69 @@ where
     }
 }
 
+impl<P, const N: usize> DoubleEndedIterator for ArrayBitQueue<P, N>
+where
+    P: Primitive
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // Blocks after active one are in place. Active one - at 0.
+        for element_index in (self.bit_block_index+1..N).rev() {
+            let block_iter = unsafe{ self.bit_block_iters.get_unchecked_mut(element_index) };
+            if let Some(index) = block_iter.next_back() {
+                return Some(element_index * size_of::<P>() * 8 + index);
+            }
+        }
+        self.bit_block_iters[0].next_back()
+            .map(|index| self.bit_block_index * size_of::<P>() * 8 + index)
+    }
+}
 
+#[cfg(test)]
+mod tests{
+    use itertools::assert_equal;
+    use super::*;
+
+    #[test]
+    fn double_ended_test(){
+        let bits = [0, 3, 63, 64, 100, 127, 130, 255];
+        let mut array = [0u64; 4];
+        for &i in &bits {
+            array[i / 64] |= 1 << (i % 64);
+        }
+        
+        assert_equal(ArrayBitQueue::new(array).rev(), bits.iter().copied().rev());
+        assert_eq!(ArrayBitQueue::new(array).current_back(), 255);
+        
+        let mut q = ArrayBitQueue::new(array);
+        assert_eq!(q.next(), Some(0));
+        assert_eq!(q.next_back(), Some(255));
+        assert_eq!(q.next(), Some(3));
+        assert_eq!(q.next(), Some(63));
+        assert_eq!(q.next_back(), Some(130));
+        assert_eq!(q.next_back(), Some(127));
+        assert_equal(q.clone(), [64, 100]);
+        assert_equal(q.rev(), [100, 64]);
+        
+        let mut q = ArrayBitQueue::new(array);
+        q.trim_after(127);
+        assert_eq!(q.current_back(), 127);
+        q.trim_to(64);
+        assert_equal(q, [64, 100, 127]);
+        
+        let mut q = ArrayBitQueue::new(array);
+        q.trim_after(129);
+        assert_equal(q.rev(), [127, 100, 64, 63, 3, 0]);
+        
+        let mut q = ArrayBitQueue::new(array);
+        q.trim_to(64);
+        q.trim_after(63);
+        assert_eq!(q.current_back(), 256);
+        assert_eq!(q.next(), None);
+        
+        let mut q = PrimitiveBitQueue::new(array[0]);
+        q.trim_after(62);
+        assert_eq!(q.current_back(), 3);
+        assert_equal(q.rev(), [3, 0]);
+    }
+}
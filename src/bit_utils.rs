@@ -190,3 +190,18 @@ where
     }
 }
 
+impl<P> DoubleEndedIterator for OneBitsIter<P>
+where
+    P: Primitive,
+{
+    #[inline(always)]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if !self.element.is_zero() {
+            let index = size_of::<P>() * 8 - 1 - self.element.leading_zeros() as usize;
+            self.element ^= P::ONE << index;
+            Some(index)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use std::cmp::Ordering;
+use std::ops::ControlFlow;
+use std::ops::ControlFlow::{Break, Continue};
+use crate::{BitBlock, HibitTree, HibitTreeCursor, HibitTreeData, HibitTreeTypes, RegularHibitTree};
+use crate::const_utils::{ConstInteger, ConstUsize};
+
+type Cursor<'a, T> = <T as HibitTreeTypes<'a>>::Cursor;
+
+/// `Lhs` data can be compared for equality with `Self` data.
+///
+/// Implemented for all [RegularHibitTree] pairs, where
+/// `for<'a> HibitTreeData<'a, Lhs>: PartialEq<HibitTreeData<'a, Self>>`.
+///
+/// Exists because Rust can not infer types through such bound directly:
+/// with it, `a == b` fails to compile if `b` type is not known yet.
+/// Implemented for the right-hand tree for the same reason.
+pub trait DataPartialEq<Lhs: RegularHibitTree>: RegularHibitTree {
+    fn data_eq<'a>(l: HibitTreeData<'a, Lhs>, r: HibitTreeData<'a, Self>) -> bool;
+}
+
+impl<Lhs, Rhs> DataPartialEq<Lhs> for Rhs
+where
+    Lhs: RegularHibitTree,
+    Rhs: RegularHibitTree,
+    for<'a> HibitTreeData<'a, Lhs>: PartialEq<HibitTreeData<'a, Rhs>>
+{
+    #[inline]
+    fn data_eq<'a>(l: HibitTreeData<'a, Lhs>, r: HibitTreeData<'a, Self>) -> bool {
+        l == r
+    }
+}
+
+/// `Lhs` data can be compared for ordering with `Self` data.
+///
+/// [PartialOrd] counterpart of [DataPartialEq].
+pub trait DataPartialOrd<Lhs: RegularHibitTree>: DataPartialEq<Lhs> {
+    fn data_partial_cmp<'a>(l: HibitTreeData<'a, Lhs>, r: HibitTreeData<'a, Self>) -> Option<Ordering>;
+}
+
+impl<Lhs, Rhs> DataPartialOrd<Lhs> for Rhs
+where
+    Lhs: RegularHibitTree,
+    Rhs: RegularHibitTree,
+    for<'a> HibitTreeData<'a, Lhs>: PartialOrd<HibitTreeData<'a, Rhs>>
+{
+    #[inline]
+    fn data_partial_cmp<'a>(l: HibitTreeData<'a, Lhs>, r: HibitTreeData<'a, Self>) -> Option<Ordering> {
+        l.partial_cmp(&r)
+    }
+}
+
+/// Compare two [RegularHibitTree]s for equality.
+///
+/// Trees are equal if they have the same keys, and values at the same keys
+/// are equal.
+///
+/// If both trees are [EXACT_HIERARCHY] - nodes compared by bitmasks
+/// level by level, and mismatched subtrees discarded without touching data.
+/// Otherwise - trees are compared element by element, in key order.
+///
+/// [EXACT_HIERARCHY]: HibitTree::EXACT_HIERARCHY
+///
+/// ```
+/// # use hibit_tree::{DenseTree, hibit_eq, intersection, map};
+/// let mut a: DenseTree<usize, 2> = Default::default();
+/// let mut b: DenseTree<usize, 2> = Default::default();
+/// a.insert(1, 1);
+/// a.insert(2, 2);
+/// b.insert(2, 2);
+/// b.insert(3, 3);
+///
+/// let mut c: DenseTree<usize, 2> = Default::default();
+/// c.insert(2, 2);
+///
+/// fn left<'a>((l, _): (&'a usize, &'a usize)) -> &'a usize { l }
+/// let i = map(intersection(&a, &b), left);
+/// assert!(hibit_eq(&i, &c));
+/// assert!(c == i);
+/// ```
+pub fn hibit_eq<T0, T1>(t0: &T0, t1: &T1) -> bool
+where
+    T0: RegularHibitTree,
+    T1: RegularHibitTree<
+        LevelMask  = <T0 as HibitTree>::LevelMask,
+        LevelCount = <T0 as HibitTree>::LevelCount,
+    >,
+    T1: DataPartialEq<T0>,
+{
+    if T0::EXACT_HIERARCHY & T1::EXACT_HIERARCHY {
+        let mut c0 = Cursor::<T0>::new(t0);
+        let mut c1 = Cursor::<T1>::new(t1);
+        unsafe{
+            exact_eq(t0, &mut c0, t1, &mut c1, ConstUsize::<0>, 0)
+        }
+    } else {
+        let mut i0 = t0.iter();
+        let mut i1 = t1.iter();
+        loop {
+            match (i0.next(), i1.next()) {
+                (None, None) => return true,
+                (Some((k0, d0)), Some((k1, d1))) => {
+                    if k0 != k1 || !T1::data_eq(d0, d1) {
+                        return false;
+                    }
+                },
+                _ => return false,
+            }
+        }
+    }
+}
+
+#[inline(always)]
+unsafe fn exact_eq<'a, T0, T1, N>(
+    t0: &'a T0, c0: &mut Cursor<'a, T0>,
+    t1: &'a T1, c1: &mut Cursor<'a, T1>,
+    n: N, index: usize
+) -> bool
+where
+    T0: RegularHibitTree,
+    T1: RegularHibitTree<
+        LevelMask  = <T0 as HibitTree>::LevelMask,
+        LevelCount = <T0 as HibitTree>::LevelCount,
+    >,
+    T1: DataPartialEq<T0>,
+    N: ConstInteger,
+{
+    let mask0 = c0.select_level_node_unchecked(t0, n, index);
+    let mask1 = c1.select_level_node_unchecked(t1, n, index);
+
+    // Exact hierarchy - different masks means different key sets.
+    if mask0 != mask1 {
+        return false;
+    }
+
+    let ctrl = if N::VALUE == T0::LevelCount::VALUE - 1 {
+        mask0.traverse_bits(|i|{
+            let d0 = c0.data_unchecked(t0, i);
+            let d1 = c1.data_unchecked(t1, i);
+            if T1::data_eq(d0, d1) { Continue(()) } else { Break(()) }
+        })
+    } else {
+        mask0.traverse_bits(|i|{
+            if exact_eq(t0, c0, t1, c1, n.inc(), i) { Continue(()) } else { Break(()) }
+        })
+    };
+    ctrl.is_continue()
+}
+
+/// Lexicographically compare two [RegularHibitTree]s.
+///
+/// Trees compared as ordered sequences of `(key, value)` pairs,
+/// like [BTreeMap]s do.
+///
+/// If both trees are [EXACT_HIERARCHY] - nodes compared by bitmasks
+/// level by level. The first mismatched bit decides the result, and
+/// only common subtrees before it are descended.
+/// Otherwise - trees are compared element by element, in key order.
+///
+/// [BTreeMap]: std::collections::BTreeMap
+/// [EXACT_HIERARCHY]: HibitTree::EXACT_HIERARCHY
+///
+/// ```
+/// # use std::cmp::Ordering;
+/// # use hibit_tree::{DenseTree, hibit_cmp};
+/// let mut a: DenseTree<usize, 2> = Default::default();
+/// let mut b: DenseTree<usize, 2> = Default::default();
+/// a.insert(1, 1);
+/// a.insert(5, 5);
+/// b.insert(1, 1);
+/// b.insert(2, 2);
+///
+/// // key 2 < key 5
+/// assert_eq!(hibit_cmp(&a, &b), Some(Ordering::Greater));
+/// assert!(a > b);
+/// ```
+pub fn hibit_cmp<T0, T1>(t0: &T0, t1: &T1) -> Option<Ordering>
+where
+    T0: RegularHibitTree,
+    T1: RegularHibitTree<
+        LevelMask  = <T0 as HibitTree>::LevelMask,
+        LevelCount = <T0 as HibitTree>::LevelCount,
+    >,
+    T1: DataPartialOrd<T0>,
+{
+    if T0::EXACT_HIERARCHY & T1::EXACT_HIERARCHY {
+        let mut c0 = Cursor::<T0>::new(t0);
+        let mut c1 = Cursor::<T1>::new(t1);
+        let ctrl = unsafe{
+            exact_cmp(t0, &mut c0, t1, &mut c1, ConstUsize::<0>, 0, false, false)
+        };
+        match ctrl {
+            Continue(()) => Some(Ordering::Equal),
+            Break(ordering) => ordering
+        }
+    } else {
+        let mut i0 = t0.iter();
+        let mut i1 = t1.iter();
+        loop {
+            match (i0.next(), i1.next()) {
+                (None, None) => return Some(Ordering::Equal),
+                (None, Some(_)) => return Some(Ordering::Less),
+                (Some(_), None) => return Some(Ordering::Greater),
+                (Some((k0, d0)), Some((k1, d1))) => {
+                    match k0.cmp(&k1) {
+                        Ordering::Equal => {},
+                        ordering => return Some(ordering)
+                    }
+                    match T1::data_partial_cmp(d0, d1) {
+                        Some(Ordering::Equal) => {},
+                        ordering => return ordering
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// `more0`/`more1` - does tree have elements after this subtree.
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+unsafe fn exact_cmp<'a, T0, T1, N>(
+    t0: &'a T0, c0: &mut Cursor<'a, T0>,
+    t1: &'a T1, c1: &mut Cursor<'a, T1>,
+    n: N, index: usize,
+    more0: bool, more1: bool,
+) -> ControlFlow<Option<Ordering>>
+where
+    T0: RegularHibitTree,
+    T1: RegularHibitTree<
+        LevelMask  = <T0 as HibitTree>::LevelMask,
+        LevelCount = <T0 as HibitTree>::LevelCount,
+    >,
+    T1: DataPartialOrd<T0>,
+    N: ConstInteger,
+{
+    let mask0 = c0.select_level_node_unchecked(t0, n, index);
+    let mask1 = c1.select_level_node_unchecked(t1, n, index);
+
+    // Remaining bits count, after current one.
+    let mut rem0 = mask0.count_ones();
+    let mut rem1 = mask1.count_ones();
+
+    let mut out = Continue(());
+    let mut union = mask0.clone();
+    union |= &mask1;
+    let _ = union.traverse_bits(|i|{
+        let in0 = mask0.get_bit(i);
+        let in1 = mask1.get_bit(i);
+        rem0 -= in0 as usize;
+        rem1 -= in1 as usize;
+
+        out = if !in1 {
+            // Next key of t0 is lesser than any remaining in t1.
+            let more1 = more1 | (rem1 != 0);
+            Break(Some(if more1 { Ordering::Less } else { Ordering::Greater }))
+        } else if !in0 {
+            let more0 = more0 | (rem0 != 0);
+            Break(Some(if more0 { Ordering::Greater } else { Ordering::Less }))
+        } else if N::VALUE == T0::LevelCount::VALUE - 1 {
+            let d0 = c0.data_unchecked(t0, i);
+            let d1 = c1.data_unchecked(t1, i);
+            match T1::data_partial_cmp(d0, d1) {
+                Some(Ordering::Equal) => Continue(()),
+                ordering => Break(ordering)
+            }
+        } else {
+            exact_cmp(
+                t0, c0, t1, c1, n.inc(), i,
+                more0 | (rem0 != 0), more1 | (rem1 != 0)
+            )
+        };
+
+        if out.is_continue() { Continue(()) } else { Break(()) }
+    });
+    out
+}
+
+/// Implements [PartialEq] and [PartialOrd] against any [RegularHibitTree]
+/// with the same configuration.
+macro_rules! impl_hibit_tree_cmp {
+    ([$($generics:tt)*] $t:ty $(, $($bounds:tt)*)?) => {
+        impl<$($generics)*, Rhs> PartialEq<Rhs> for $t
+        where
+            Self: $crate::RegularHibitTree,
+            Rhs: $crate::RegularHibitTree<
+                LevelMask  = <Self as $crate::HibitTree>::LevelMask,
+                LevelCount = <Self as $crate::HibitTree>::LevelCount,
+            >,
+            Rhs: $crate::DataPartialEq<Self>,
+            $($($bounds)*)?
+        {
+            /// See [hibit_eq](crate::hibit_eq).
+            #[inline]
+            fn eq(&self, other: &Rhs) -> bool {
+                $crate::hibit_eq(self, other)
+            }
+        }
+
+        impl<$($generics)*, Rhs> PartialOrd<Rhs> for $t
+        where
+            Self: $crate::RegularHibitTree,
+            Rhs: $crate::RegularHibitTree<
+                LevelMask  = <Self as $crate::HibitTree>::LevelMask,
+                LevelCount = <Self as $crate::HibitTree>::LevelCount,
+            >,
+            Rhs: $crate::DataPartialOrd<Self>,
+            $($($bounds)*)?
+        {
+            /// See [hibit_cmp](crate::hibit_cmp).
+            #[inline]
+            fn partial_cmp(&self, other: &Rhs) -> Option<std::cmp::Ordering> {
+                $crate::hibit_cmp(self, other)
+            }
+        }
+    };
+}
+
+pub(crate) use impl_hibit_tree_cmp;
+
+#[cfg(test)]
+mod tests{
+    use std::cmp::Ordering;
+    use std::collections::BTreeMap;
+    use rand::{Rng, SeedableRng};
+    use crate::{DenseTree, hibit_cmp, hibit_eq, intersection, map, SparseTree, config};
+
+    type Tree = DenseTree<usize, 2>;
+    
+    fn left<'a>((l, _): (&'a usize, &'a usize)) -> &'a usize { l }
+    
+    fn tree(keys: &[usize]) -> Tree {
+        let mut t = Tree::default();
+        for &k in keys {
+            t.insert(k, k);
+        }
+        t
+    }
+    
+    #[test]
+    fn eq_test(){
+        let a = tree(&[1, 5, 300, 4000]);
+        let b = tree(&[4000, 300, 5, 1]);
+        let c = tree(&[1, 5, 300]);
+        assert!(hibit_eq(&a, &b));
+        assert!(!hibit_eq(&a, &c));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        
+        // lazy vs concrete 
+        let i = map(intersection(&a, &c), left);
+        assert!(hibit_eq(&i, &c));
+        assert!(c == i);
+        assert!(i != a);
+
+        // DenseTree vs SparseTree
+        let mut s: SparseTree<config::width_64::depth_2, usize> = Default::default();
+        for k in [1, 5, 300] {
+            s.insert(k, k);
+        }
+        assert!(c == s);
+        assert!(a != s);
+        s.insert(300, 0);
+        assert!(c != s);
+    }
+    
+    #[test]
+    fn lazy_ops_eq_test(){
+        use crate::{difference, filter, filter_map, shift, symmetric_difference};
+        let a = tree(&[1, 5, 300, 4000]);
+        let b = tree(&[5, 4000, 4001]);
+
+        assert!(difference(&a, &b) == tree(&[1, 300]));
+        assert!(difference(&a, &b) != a);
+        assert!(difference(&a, &b) > a);
+
+        let s = symmetric_difference(&a, &b);
+        let a2 = a.clone();
+        assert!(s == symmetric_difference(&a2, &b));
+        // Same keys, but sides swapped.
+        assert!(s != symmetric_difference(&b, &a));
+        assert!(s != symmetric_difference(&a, &a));
+
+        assert!(filter(&a, |v: &usize| *v < 400) == tree(&[1, 5, 300]));
+        assert!(filter(&a, |v: &usize| *v < 400) < a);
+        let c = tree(&[5, 300, 4000]);
+        assert!(filter_map(&a, |v: &usize| (*v > 4).then_some(*v)) == map(&c, |v: &usize| *v));
+        assert!(filter_map(&a, |v: &usize| (*v > 5).then_some(*v)) > map(&c, |v: &usize| *v));
+
+        let shifted: Tree = [(65, 1), (69, 5)].into_iter().collect();
+        assert!(shift(&tree(&[1, 5]), 64) == shifted);
+        assert!(shift(&tree(&[1, 5]), 64) != tree(&[65, 69]));
+        assert!(shift(&tree(&[1, 5]), 64) > tree(&[1, 5]));
+    }
+
+    #[test]
+    fn cmp_test(){
+        let a = tree(&[1, 5]);
+        let b = tree(&[1, 2]);
+        assert_eq!(hibit_cmp(&a, &b), Some(Ordering::Greater));
+        assert_eq!(hibit_cmp(&b, &a), Some(Ordering::Less));
+        assert_eq!(hibit_cmp(&a, &a), Some(Ordering::Equal));
+        
+        // prefix
+        let c = tree(&[1]);
+        assert_eq!(hibit_cmp(&a, &c), Some(Ordering::Greater));
+        assert_eq!(hibit_cmp(&c, &a), Some(Ordering::Less));
+        
+        // lazy vs concrete, must match
+        let i = map(intersection(&a, &c), left);
+        assert_eq!(hibit_cmp(&i, &c), Some(Ordering::Equal));
+        assert!(i < a);
+    }
+    
+    #[test]
+    fn fuzzy_cmp_test(){
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0xe15bb9db3dee3a0f);
+        for _ in 0..1000 {
+            let mut trees: [(Tree, BTreeMap<usize, usize>); 2] = Default::default();
+            for (t, m) in &mut trees {
+                let len = rng.gen_range(0..6);
+                for _ in 0..len {
+                    // Small ranges to provoke collisions. 
+                    let k = if rng.gen_bool(0.5) { rng.gen_range(0..8) } else { rng.gen_range(0..4096) };
+                    let v = rng.gen_range(0..2);
+                    t.insert(k, v);
+                    m.insert(k, v);
+                }
+            }
+            let [(t0, m0), (t1, m1)] = &trees;
+            assert_eq!(hibit_cmp(t0, t1), m0.partial_cmp(m1));
+            assert_eq!(hibit_eq(t0, t1), m0 == m1);
+            
+            // non-exact path
+            let i0 = map(intersection(t0, t0), left);
+            assert_eq!(hibit_cmp(&i0, t1), m0.partial_cmp(m1));
+            assert_eq!(hibit_eq(&i0, t1), m0 == m1);
+        }
+    }
+}
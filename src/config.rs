@@ -2,27 +2,37 @@
 //! 
 //! General rule: use the smallest depth possible.
 //!
+//! Level blocks, elements and keys are allocated with [NodeAllocator] `A`,
+//! [Global] by default. `depth_1` have only one block, stored inline.
+//! 
+//! ```
+//! # use hibit_tree::{config, Global, SparseTree};
+//! type Tree = SparseTree<config::width_64::depth_3<Global>, usize>;
+//! ```
+//!
 //! [SparseTree]: crate::SparseTree 
+//! [NodeAllocator]: crate::NodeAllocator
 
 pub use crate::sparse_tree_levels::SparseTreeLevels;
 
+use crate::Global;
 use crate::level::{IntrusiveListLevel, SingleBlockLevel};
 use crate::level_block::Block;
 
 macro_rules! generate {
     ($LO: ty, $L1: ty, $LN: ty) => {
-        type L0 = SingleBlockLevel<$LO>;
-        type L1 = IntrusiveListLevel<$L1>;
-        type LN = IntrusiveListLevel<$LN>;
+        type L0<A> = SingleBlockLevel<$LO, A>;
+        type L1<A> = IntrusiveListLevel<$L1, A>;
+        type LN<A> = IntrusiveListLevel<$LN, A>;
         
-        pub type depth_1 = (L0,);
-        pub type depth_2 = (L0, L1);
-        pub type depth_3 = (L0, L1, LN);
-        pub type depth_4 = (L0, L1, LN, LN);
-        pub type depth_5 = (L0, L1, LN, LN, LN);
-        pub type depth_6 = (L0, L1, LN, LN, LN, LN);
-        pub type depth_7 = (L0, L1, LN, LN, LN, LN, LN);
-        pub type depth_8 = (L0, L1, LN, LN, LN, LN, LN, LN);
+        pub type depth_1<A = Global> = (L0<A>,);
+        pub type depth_2<A = Global> = (L0<A>, L1<A>);
+        pub type depth_3<A = Global> = (L0<A>, L1<A>, LN<A>);
+        pub type depth_4<A = Global> = (L0<A>, L1<A>, LN<A>, LN<A>);
+        pub type depth_5<A = Global> = (L0<A>, L1<A>, LN<A>, LN<A>, LN<A>);
+        pub type depth_6<A = Global> = (L0<A>, L1<A>, LN<A>, LN<A>, LN<A>, LN<A>);
+        pub type depth_7<A = Global> = (L0<A>, L1<A>, LN<A>, LN<A>, LN<A>, LN<A>, LN<A>);
+        pub type depth_8<A = Global> = (L0<A>, L1<A>, LN<A>, LN<A>, LN<A>, LN<A>, LN<A>, LN<A>);
     };
 }
 
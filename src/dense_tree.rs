@@ -8,24 +8,54 @@
 mod tests;
 
 mod from;
+mod from_iter;
+mod entry;
+mod retain;
+mod set_ops;
 mod node;
+mod node_pool;
+mod memory_stats;
 
-use std::{mem, ptr};
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+
+use std::{fmt, mem, ptr};
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
-use crate::{BitBlock, Index, HibitTreeCursorTypes, HibitTreeTypes};
+use crate::{BitBlock, Global, Index, HibitTreeCursorTypes, HibitTreeTypes, NodeAllocator};
 use crate::bit_queue::BitQueue;
 use crate::const_utils::{const_loop, ConstArray, ConstArrayType, ConstBool, ConstFalse, ConstInteger, ConstTrue, ConstUsize};
 use crate::level_indices;
 use crate::hibit_tree::{HibitTree, HibitTreeCursor};
 use crate::utils::{Array, Borrowable, Primitive};
+use crate::alloc_vec::AllocVec;
+use crate::cmp::impl_hibit_tree_cmp;
 
 use node::{NodePtr, empty_node};
+use node_pool::NodePool;
 
 type Mask = u64;
 
-// TODO: On insert check that capacity does not exceeds DataIndex capacity. 
-//       Can be usize as well.
-type DataIndex = u32;
+/// [DenseTree] `data` index, stored in terminal nodes.
+/// 
+/// Limits [DenseTree] elements count to `DataIndex::MAX`.
+pub trait DataIndex: Primitive {}
+impl DataIndex for u8 {}
+impl DataIndex for u16 {}
+impl DataIndex for u32 {}
+impl DataIndex for u64 {}
+impl DataIndex for usize {}
+
+/// # Panics
+/// 
+/// Panics if `index` does not fit into `I`.
+#[inline]
+fn to_data_index<I: DataIndex>(index: usize) -> I {
+    assert!(
+        index <= I::MAX.as_usize(),
+        "DenseTree elements count exceeds `{}` data index capacity", std::any::type_name::<I>()
+    );
+    I::from_usize(index)
+}
 
 /// Compressed Hierarchical Bitmap Tree.
 /// 
@@ -52,11 +82,46 @@ type DataIndex = u32;
 /// 
 /// With `bmi2` enabled, access operations are just 20% slower then 64bit [SparseTree] access.
 /// Insert and remove operations have additional performance impact too, since 
-/// they need to keep child nodes in order. 
-/// TODO: swap to non-compressed after certain threshold to amortize this.
+/// they need to keep child nodes in order. Nodes with more than 32 children
+/// switch to uncompressed storage, where insert and remove are O(1).
 /// 
 /// [SparseTree]: crate::SparseTree 
 ///
+/// # Data index
+/// 
+/// Terminal nodes store indices into elements array, of [DataIndex] type `I`.
+/// `u32` by default. Element count is limited to `I::MAX` - going past that
+/// panics. Narrower `I` makes terminal nodes smaller:
+/// 
+/// ```
+/// # use hibit_tree::DenseTree;
+/// // At most 65535 elements.
+/// let tree: DenseTree<usize, 4, u16> = [(1, 1), (1_000_000, 2)].into_iter().collect();
+/// ```
+/// 
+/// [DataIndex]: crate::dense::DataIndex
+///
+/// # Allocator
+/// 
+/// Nodes, elements and keys are allocated with [NodeAllocator] `A`,
+/// [Global] by default. Allocator with state is passed with [new_in()].
+/// 
+/// [new_in()]: Self::new_in
+///
+/// # Node pool
+/// 
+/// Nodes freed by [remove()], [clear()], [retain()] and node resizes are not
+/// returned to the allocator. They are kept in per-size free-lists, and reused
+/// by subsequent inserts. Pool is not bounded - it can hold as many nodes as
+/// tree had at its peak. Only [shrink_to_fit()] and drop release it.
+/// Pooled size is reported as [MemoryStats::free_bytes].
+/// 
+/// [remove()]: Self::remove
+/// [clear()]: Self::clear
+/// [retain()]: Self::retain
+/// [shrink_to_fit()]: Self::shrink_to_fit
+/// [MemoryStats::free_bytes]: crate::MemoryStats::free_bytes
+///
 /// # `target-feature`s
 /// 
 /// ## x86
@@ -70,15 +135,20 @@ type DataIndex = u32;
 /// 
 /// In addition, to lib's `popcnt` and `bmi1` requirement, on x86 arch
 /// CompactSparseArray also benefits from `bmi2`'s `bzhi` instruction.
-pub struct DenseTree<T, const DEPTH: usize>
+pub struct DenseTree<T, const DEPTH: usize, I: DataIndex = u32, A: NodeAllocator = Global>
 where
     ConstUsize<DEPTH>: ConstInteger
 {
     root: NodePtr,
     
+    /// Removed nodes, reused by insert.
+    node_pool: NodePool<A>,
+    
     /// First item - is a placeholder for non-existent/default element.
-    data: Vec<T>,
-    keys: Vec<usize>,
+    data: AllocVec<T, A>,
+    keys: AllocVec<usize, A>,
+    
+    data_index: PhantomData<I>,
     
     /*// TODO: Make this technique optional? Since it consumes additional mem.
     /// Position in terminal node, that points to `data` with this vec's index.
@@ -89,32 +159,88 @@ where
     terminal_node_positions: Vec<(NodePtr/*terminal_node*/, usize/*in-node index*/)>,*/
 }
 
-impl<T, const DEPTH: usize> Default for DenseTree<T, DEPTH>
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator + Clone + Default> Default for DenseTree<T, DEPTH, I, A>
 where
     ConstUsize<DEPTH>: ConstInteger
 {
     #[inline]
     fn default() -> Self {
-        // TODO: reuse somehow?
-        let mut data: Vec<T> = Vec::with_capacity(1);
-        unsafe{ data.set_len(1); }
+        Self::new_in(A::default())
+    }
+}
 
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator + Clone> DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    /// Constructs an empty tree, with nodes and elements allocated by `allocator`.
+    #[inline]
+    pub fn new_in(allocator: A) -> Self {
+        let mut keys = AllocVec::new_in(allocator.clone());
+        keys.push(usize::MAX);
+        let mut node_pool = NodePool::new(allocator.clone());
         Self{
-            root: if DEPTH == 1 {
-                NodePtr::new::<DataIndex>(node::DEFAULT_CAP, 0)                
-            } else {
-                NodePtr::new::<NodePtr>(node::DEFAULT_CAP, empty_node(ConstUsize::<1>, ConstUsize::<DEPTH>))
-            },
-            data,
-            keys: vec![usize::MAX]
+            root: Self::new_root(&mut node_pool),
+            node_pool,
+            data: unsafe{ AllocVec::with_placeholder_in(1, allocator) },
+            keys,
+            data_index: PhantomData,
         }
     }
+    
+    /// Constructs an empty tree with space for at least `capacity` elements.
+    /// 
+    /// Only elements storage is preallocated - nodes are allocated on insert.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self
+    where
+        A: Default
+    {
+        Self::with_capacity_in(capacity, A::default())
+    }
+    
+    /// [with_capacity()] with nodes and elements allocated by `allocator`.
+    /// 
+    /// [with_capacity()]: Self::with_capacity
+    #[inline]
+    pub fn with_capacity_in(capacity: usize, allocator: A) -> Self {
+        let mut this = Self::new_in(allocator);
+        this.reserve(capacity);
+        this
+    }
 }
 
-impl<T, const DEPTH: usize> DenseTree<T, DEPTH>
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> DenseTree<T, DEPTH, I, A>
 where
     ConstUsize<DEPTH>: ConstInteger    
 {
+    /// Nodes and elements allocator.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        self.node_pool.allocator()
+    }
+    
+    /// Empty root node.
+    #[inline]
+    fn new_root(node_pool: &mut NodePool<A>) -> NodePtr {
+        if DEPTH == 1 {
+            NodePtr::new::<I>(node_pool, node::DEFAULT_CAP, I::ZERO)                
+        } else {
+            NodePtr::new::<NodePtr>(node_pool, node::DEFAULT_CAP, empty_node(ConstUsize::<1>, ConstUsize::<DEPTH>))
+        }
+    }
+    
+    /// Replace empty root with `root`.
+    /// 
+    /// # Safety
+    /// 
+    /// `root` hierarchy must refer all `data` elements.
+    #[inline]
+    unsafe fn replace_root(&mut self, root: NodePtr) {
+        let old = mem::replace(&mut self.root, root);
+        old.drop_node_with_childs::<ConstUsize<0>, DEPTH, I>(&mut self.node_pool);
+    }
+    
     #[inline]
     fn get_or_insert_impl(
         &mut self, 
@@ -122,56 +248,15 @@ where
         insert: impl ConstBool,
         value_fn: impl FnOnce() -> T
     ) -> &mut T {
-        let indices = level_indices::<Mask, ConstUsize<DEPTH>>(index);
-        
-        // get terminal node pointing to data
-        let mut node = &mut self.root;
-        const_loop!(N in 0..{DEPTH-1} => {
-            let inner_index = indices.as_ref()[N];
-            unsafe{
-                let mut node_ptr = *node;
-                /*child*/ node = if node_ptr.header().contains(inner_index) {
-                    node_ptr.get_child_mut(inner_index)
-                } else {
-                    // TODO: insert node with already inserted ONE element 
-                    //       all down below. And immediately exit loop?
-                    //       BENCHMARK change.
-
-                    // update a child pointer with a (possibly) new address
-                    let (mut inserted_ptr, new_node) =
-                        if N == DEPTH-2 /* child node is terminal */ {
-                            node_ptr.insert( inner_index, NodePtr::new::<DataIndex>(node::DEFAULT_CAP, 0) )
-                        } else {
-                            // N + 2, because we point from child, and to it's child
-                            let empty_child = empty_node(ConstUsize::<N>.inc().inc(), ConstUsize::<DEPTH>);
-                            node_ptr.insert( inner_index, NodePtr::new::<NodePtr>(node::DEFAULT_CAP, empty_child) )
-                        };
-                    *node = new_node;   // This is actually optional
-                    inserted_ptr.as_mut()
-                }
-            }
-        });
-     
-        // now fetch data
-        unsafe{
-            let node_ptr = *node;
-            let inner_index = *indices.as_ref().last().unwrap_unchecked();
-            
-            let data_index = if node_ptr.header().contains(inner_index) {
-                let data_index = node_ptr.get_child::<DataIndex>(inner_index).as_usize();
+        match self.entry_impl(index) {
+            Entry::Occupied(entry) => {
+                let value = entry.into_mut();
                 /*const*/ if insert.value(){
-                    *self.data.get_unchecked_mut(data_index) = value_fn();                     
+                    *value = value_fn();
                 }
-                data_index
-            } else {
-                let i = self.data.len(); 
-                self.data.push(value_fn());
-                self.keys.push(index);
-                let (_, new_node) = node_ptr.insert(inner_index, i as DataIndex);
-                *node = new_node;
-                i
-            };
-            self.data.get_unchecked_mut(data_index)
+                value
+            }
+            Entry::Vacant(entry) => entry.insert(value_fn())
         }
     }    
     
@@ -232,57 +317,175 @@ where
             let indices = level_indices::<Mask, ConstUsize<DEPTH>>(index);
             let branch = self.get_branch(&indices);
 
-            let terminal_node = branch.as_ref().last().unwrap_unchecked();
+            let terminal_node = if DEPTH == 1 {
+                self.root
+            } else {
+                *branch.as_ref().last().unwrap_unchecked()
+            };
             let terminal_inner_index = *indices.as_ref().last().unwrap_unchecked();
 
-            let data_index = terminal_node.get_child::<DataIndex>(terminal_inner_index).as_usize();
+            let data_index = terminal_node.get_child::<I>(terminal_inner_index).as_usize();
             
             if *self.keys.get_unchecked(data_index) == index {
-                terminal_node.remove::<DataIndex>(terminal_inner_index);
-
-                // 1. Try remove empty terminal node recursively.
-                if terminal_node.header().len() == 1 /* TODO: unlikely */ {
-                    terminal_node.drop_node::<DataIndex>();
-
-                    // climb up the tree, and remove empty nodes
-                    const_loop!(N in 0..{DEPTH-1} rev => 'out: {
-                        let node = if N == 0 {
-                            self.root
-                        } else {
-                            branch.as_ref()[N-1]
-                        };
-                        
-                        node.remove::<NodePtr>(indices.as_ref()[N]);
-                        if node.header().len() != 1 {
-                            break 'out;
-                        } 
-                        
-                        /*const*/ if N != 0 /*don't touch root*/ {
-                            node.drop_node::<NodePtr>();
-                        }                        
-                    });
-                }
-
-                // 2. Swap remove key + update swapped terminal node
-                {
-                    let last_key = self.keys.pop().unwrap_unchecked();
-                    if last_key != index {
-                        *self.keys.get_unchecked_mut(data_index) = last_key;
-
-                        let indices = level_indices::<Mask, ConstUsize<DEPTH>>(last_key);
-                        let (node, inner_index) = self.get_terminal_node(indices.as_ref());                    
-                        *node.get_child_mut::<DataIndex>(inner_index) = data_index as DataIndex; 
-                    }    
-                }
-                
-                // 3. Remove data        
-                let value = self.data.swap_remove(data_index);                
-                Some(value)                
+                Some(self.remove_existing(index, indices.as_ref(), branch.as_ref(), data_index))
             } else {
                 None
             }
         }   
     }
+    
+    /// Element with the smallest index. Same as [first()].
+    /// 
+    /// [first()]: HibitTree::first
+    #[inline]
+    pub fn first_key_value(&self) -> Option<(usize, &T)> {
+        self.first()
+    }
+    
+    /// Element with the largest index. Same as [last()].
+    /// 
+    /// [last()]: HibitTree::last
+    #[inline]
+    pub fn last_key_value(&self) -> Option<(usize, &T)> {
+        self.last()
+    }
+    
+    /// Remove and return the element with the smallest index.
+    /// 
+    /// Emptied nodes are removed, just like with [remove()].
+    /// 
+    /// [remove()]: Self::remove
+    #[inline]
+    pub fn pop_first(&mut self) -> Option<(usize, T)> {
+        let (index, _) = self.first()?;
+        self.remove(index).map(|value| (index, value))
+    }
+    
+    /// Remove and return the element with the largest index.
+    /// 
+    /// Emptied nodes are removed, just like with [remove()].
+    /// 
+    /// [remove()]: Self::remove
+    #[inline]
+    pub fn pop_last(&mut self) -> Option<(usize, T)> {
+        let (index, _) = self.last()?;
+        self.remove(index).map(|value| (index, value))
+    }
+    
+    /// # Safety
+    /// 
+    /// * Element at `index` must exist.
+    /// * `branch` - nodes at `indices` path, root excluded.
+    /// * `data_index` - element position in `data`. 
+    unsafe fn remove_existing(
+        &mut self, 
+        index: usize,
+        indices: &[usize],
+        branch: &[NodePtr],
+        data_index: usize
+    ) -> T {
+        let terminal_node = if DEPTH == 1 {
+            self.root
+        } else {
+            *branch.get_unchecked(DEPTH-2)
+        };
+        let terminal_inner_index = *indices.get_unchecked(DEPTH-1);
+        
+        terminal_node.remove::<I>(terminal_inner_index);
+
+        // 1. Try remove empty terminal node recursively.
+        if DEPTH != 1 /*don't touch root*/ && terminal_node.header().len() == 1 /* TODO: unlikely */ {
+            terminal_node.drop_node::<I>(&mut self.node_pool);
+
+            // climb up the tree, and remove empty nodes
+            const_loop!(N in 0..{DEPTH-1} rev => 'out: {
+                let node = if N == 0 {
+                    self.root
+                } else {
+                    branch[N-1]
+                };
+                
+                node.remove::<NodePtr>(indices[N]);
+                if node.header().len() != 1 {
+                    break 'out;
+                } 
+                
+                /*const*/ if N != 0 /*don't touch root*/ {
+                    node.drop_node::<NodePtr>(&mut self.node_pool);
+                }                        
+            });
+        }
+
+        // 2. Swap remove key + update swapped terminal node
+        {
+            let last_key = self.keys.pop().unwrap_unchecked();
+            if last_key != index {
+                *self.keys.get_unchecked_mut(data_index) = last_key;
+
+                let indices = level_indices::<Mask, ConstUsize<DEPTH>>(last_key);
+                let (node, inner_index) = self.get_terminal_node(indices.as_ref());                    
+                *node.get_child_mut::<I>(inner_index) = I::from_usize(data_index); 
+            }    
+        }
+        
+        // 3. Remove data        
+        self.data.swap_remove(data_index)
+    }
+
+    /// Number of elements.
+    #[inline]
+    pub fn len(&self) -> usize {
+        // skip first element
+        self.keys.len() - 1
+    }
+    
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Remove all elements.
+    /// 
+    /// Keeps allocated capacity. Nodes go to the node pool - see [shrink_to_fit()].
+    /// 
+    /// [shrink_to_fit()]: Self::shrink_to_fit
+    #[inline]
+    pub fn clear(&mut self) {
+        // drop node hierarchy
+        let root = mem::replace(&mut self.root, Self::new_root(&mut self.node_pool));
+        unsafe{ root.drop_node_with_childs::<ConstUsize<0>, DEPTH, I>(&mut self.node_pool); }
+        
+        // drop values, except first placeholder
+        self.keys.truncate(1);
+        self.data.truncate(1);
+    }
+    
+    /// Number of elements tree can hold without reallocating elements storage.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        // skip first element
+        self.data.capacity().min(self.keys.capacity()) - 1
+    }
+    
+    /// Reserve space for at least `additional` more elements.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.data.reserve(additional);
+        self.keys.reserve(additional);
+    }
+    
+    /// Shrink elements storage as much as possible, and return pooled nodes
+    /// to the allocator.
+    /// 
+    /// Nodes, deallocated by remove, are kept in pool and reused by 
+    /// subsequent inserts. So insert/remove heavy workloads do not hit 
+    /// allocator on each structural change.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.data.shrink_to_fit();
+        self.keys.shrink_to_fit();
+        self.node_pool.release();
+    }
 
     /// Key-values in arbitrary order.
     #[inline]
@@ -308,6 +511,16 @@ where
         }
     }
     
+    /// Forget all values, as if they were moved out.
+    /// 
+    /// # Safety
+    /// 
+    /// Tree is left in inconsistent state - it must be dropped right after.
+    #[inline]
+    pub(crate) unsafe fn forget_values(&mut self) {
+        self.data.set_len(1);
+    }
+    
     #[inline]
     unsafe fn drop_impl(&mut self){
         // drop values
@@ -319,12 +532,12 @@ where
         self.data.set_len(0);
         
         // drop node hierarchy
-        self.root.drop_node_with_childs::<ConstUsize<0>, DEPTH>()         
+        self.root.drop_node_with_childs::<ConstUsize<0>, DEPTH, I>(&mut self.node_pool)
     }
 }
 
 #[cfg(feature = "may_dangle")]
-unsafe impl<#[may_dangle] T, const DEPTH: usize> Drop for DenseTree<T, DEPTH> {
+unsafe impl<#[may_dangle] T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> Drop for DenseTree<T, DEPTH, I, A> {
     #[inline]
     fn drop(&mut self) {
         unsafe{ self.drop_impl(); }
@@ -332,7 +545,7 @@ unsafe impl<#[may_dangle] T, const DEPTH: usize> Drop for DenseTree<T, DEPTH> {
 }
 
 #[cfg(not(feature = "may_dangle"))]
-impl<T, const DEPTH: usize> Drop for DenseTree<T, DEPTH>
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> Drop for DenseTree<T, DEPTH, I, A>
 where
     ConstUsize<DEPTH>: ConstInteger
 {
@@ -342,16 +555,74 @@ where
     }
 }
 
-impl<'a, T, const DEPTH: usize> HibitTreeTypes<'a> for DenseTree<T, DEPTH>
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator + Clone> Clone for DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger,
+    T: Clone
+{
+    /// Copies node hierarchy as is, without re-inserting keys.
+    #[inline]
+    fn clone(&self) -> Self {
+        let mut node_pool = NodePool::new(self.allocator().clone());
+        let mut out = Self{
+            root: unsafe{ self.root.clone_node_with_childs::<ConstUsize<0>, DEPTH, I>(&mut node_pool) },
+            node_pool,
+            data: unsafe{ AllocVec::with_placeholder_in(self.data.len(), self.allocator().clone()) },
+            keys: self.keys.clone(),
+            data_index: PhantomData,
+        };
+        // Fill data last - if T::clone() panics, `out` will drop
+        // only what is already there.
+        out.data.extend_from_slice(self.key_values().1);
+        out
+    }
+}
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> fmt::Debug for DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger,
+    T: fmt::Debug
+{
+    /// Key-values in key order.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl_hibit_tree_cmp!([T, const DEPTH: usize, I: DataIndex, A: NodeAllocator] DenseTree<T, DEPTH, I, A>, ConstUsize<DEPTH>: ConstInteger);
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> Eq for DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger,
+    T: Eq
+{}
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> Hash for DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger,
+    T: Hash
+{
+    /// Hashes key-values in key order.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.keys.len() - 1);
+        for (key, value) in self.iter() {
+            key.hash(state);
+            value.hash(state);
+        }
+    }
+}
+
+impl<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> HibitTreeTypes<'a> for DenseTree<T, DEPTH, I, A>
 where
     ConstUsize<DEPTH>: ConstInteger
 {
     type Data = &'a T;
     type DataUnchecked = &'a T;
-    type Cursor = Cursor<'a, T, DEPTH>;    
+    type Cursor = Cursor<'a, T, DEPTH, I, A>;    
 }
 
-impl<T, const DEPTH: usize> HibitTree for DenseTree<T, DEPTH>
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> HibitTree for DenseTree<T, DEPTH, I, A>
 where
     ConstUsize<DEPTH>: ConstInteger
 {
@@ -367,7 +638,7 @@ where
     {
         let (node, inner_index) = self.get_terminal_node(level_indices);
         // point to SOME valid item
-        let data_index = node.get_child::<DataIndex>(inner_index).as_usize();
+        let data_index = node.get_child::<I>(inner_index).as_usize();
 
         // The element may be wrong thou, so we check its index.
         if *self.keys.get_unchecked(data_index) == index {
@@ -385,7 +656,7 @@ where
     }
 }
 
-pub struct Cursor<'src, T, const DEPTH: usize>
+pub struct Cursor<'src, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator>
 where
     ConstUsize<DEPTH>: ConstInteger
 {
@@ -396,21 +667,21 @@ where
         Option<NodePtr>, 
         <ConstUsize<DEPTH> as ConstInteger>::Dec
     >,     
-    phantom_data: PhantomData<&'src T>
+    phantom_data: PhantomData<(&'src T, I, &'src A)>
 }
 
-impl<'this, 'src, T, const DEPTH: usize> HibitTreeCursorTypes<'this> for Cursor<'src, T, DEPTH>
+impl<'this, 'src, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> HibitTreeCursorTypes<'this> for Cursor<'src, T, DEPTH, I, A>
 where
     ConstUsize<DEPTH>: ConstInteger
 {
     type Data = &'src T;
 }
 
-impl<'src, T, const DEPTH: usize> HibitTreeCursor<'src> for Cursor<'src, T, DEPTH>
+impl<'src, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> HibitTreeCursor<'src> for Cursor<'src, T, DEPTH, I, A>
 where
     ConstUsize<DEPTH>: ConstInteger
 {
-    type Src = DenseTree<T, DEPTH>;
+    type Src = DenseTree<T, DEPTH, I, A>;
 
     #[inline]
     fn new(_: &'src Self::Src) -> Self {
@@ -493,11 +764,11 @@ where
         };
         
         /*// default
-        let data_index = node.get_child::<DataIndex>(level_index).as_usize() * node.contains(level_index) as usize;
+        let data_index = node.get_child::<I>(level_index).as_usize() * node.contains(level_index) as usize;
         Some(this.data.get_unchecked(data_index))*/
 
         if node.header().contains(level_index) {
-            let data_index = node.get_child::<DataIndex>(level_index).as_usize();
+            let data_index = node.get_child::<I>(level_index).as_usize();
             Some(this.data.get_unchecked(data_index))
         } else {
             None
@@ -515,12 +786,12 @@ where
             self.level_nodes.as_ref().last().unwrap_unchecked().unwrap_unchecked()
         };
             
-        let data_index = node.get_child::<DataIndex>(level_index).as_usize();
+        let data_index = node.get_child::<I>(level_index).as_usize();
         this.data.get_unchecked(data_index)
     }
 }
 
-impl<T, const DEPTH: usize> Borrowable for DenseTree<T, DEPTH>
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> Borrowable for DenseTree<T, DEPTH, I, A>
 where
     ConstUsize<DEPTH>: ConstInteger
 { type Borrowed = Self; }
\ No newline at end of file
@@ -0,0 +1,234 @@
+use std::ptr::NonNull;
+
+use crate::const_utils::{ConstInteger, ConstUsize};
+use crate::{Index, NodeAllocator, level_indices};
+
+use super::node::{self, empty_node_at, NodePtr};
+use super::{to_data_index, DenseTree, DataIndex, Mask};
+
+/// A view into a single element of [DenseTree], which may either be vacant or occupied.
+///
+/// Constructed by [DenseTree::entry].
+pub enum Entry<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    Occupied(OccupiedEntry<'a, T, DEPTH, I, A>),
+    Vacant(VacantEntry<'a, T, DEPTH, I, A>),
+}
+
+/// Existing element of [DenseTree]. Part of [Entry].
+pub struct OccupiedEntry<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    tree: &'a mut DenseTree<T, DEPTH, I, A>,
+    index: usize,
+
+    /// Nodes at `index` path, root excluded. Last element unused.
+    branch: [NodePtr; DEPTH],
+    data_index: usize,
+}
+
+/// Non-existent element of [DenseTree]. Part of [Entry].
+pub struct VacantEntry<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    tree: &'a mut DenseTree<T, DEPTH, I, A>,
+    index: usize,
+
+    /// Level of the deepest existing node at `index` path.
+    level: usize,
+
+    /// Where the pointer to the deepest existing node is stored.
+    /// `None` for root.
+    node_slot: Option<NonNull<NodePtr>>,
+}
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    /// Element at `index` for in-place manipulation.
+    ///
+    /// Tree traversed only once, whatever you do with [Entry] next.
+    #[inline]
+    pub fn entry(&mut self, index: impl Into<Index<Mask, ConstUsize<DEPTH>>>) -> Entry<'_, T, DEPTH, I, A> {
+        self.entry_impl(index.into().into())
+    }
+
+    #[inline]
+    pub(super) fn entry_impl(&mut self, index: usize) -> Entry<'_, T, DEPTH, I, A> {
+        let indices = level_indices::<Mask, ConstUsize<DEPTH>>(index);
+
+        let mut branch = [self.root; DEPTH];
+        let mut node_slot = None;
+        let mut node = self.root;
+        for (n, branch_node) in branch.iter_mut().enumerate().take(DEPTH-1) {
+            let inner_index = unsafe{ *indices.as_ref().get_unchecked(n) };
+            if !node.header().contains(inner_index) {
+                return Entry::Vacant(VacantEntry{ tree: self, index, level: n, node_slot });
+            }
+
+            let child = unsafe{ node.get_child_mut::<NodePtr>(inner_index) };
+            node = *child;
+            node_slot = Some(NonNull::from(child));
+            *branch_node = node;
+        }
+
+        let inner_index = unsafe{ *indices.as_ref().last().unwrap_unchecked() };
+        if node.header().contains(inner_index) {
+            let data_index = unsafe{ node.get_child::<I>(inner_index) }.as_usize();
+            Entry::Occupied(OccupiedEntry{ tree: self, index, branch, data_index })
+        } else {
+            Entry::Vacant(VacantEntry{ tree: self, index, level: DEPTH-1, node_slot })
+        }
+    }
+}
+
+impl<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> Entry<'a, T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    #[inline]
+    pub fn key(&self) -> usize {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    #[inline]
+    pub fn or_insert(self, value: T) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(value),
+        }
+    }
+
+    #[inline]
+    pub fn or_insert_with(self, f: impl FnOnce() -> T) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    #[inline]
+    pub fn or_insert_with_key(self, f: impl FnOnce(usize) -> T) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = f(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn or_default(self) -> &'a mut T
+    where
+        T: Default
+    {
+        self.or_insert_with(T::default)
+    }
+
+    /// Modify existing element, if any.
+    #[inline]
+    pub fn and_modify(mut self, f: impl FnOnce(&mut T)) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> OccupiedEntry<'a, T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    #[inline]
+    pub fn key(&self) -> usize {
+        self.index
+    }
+
+    #[inline]
+    pub fn get(&self) -> &T {
+        unsafe{ self.tree.data.get_unchecked(self.data_index) }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe{ self.tree.data.get_unchecked_mut(self.data_index) }
+    }
+
+    #[inline]
+    pub fn into_mut(self) -> &'a mut T {
+        unsafe{ self.tree.data.get_unchecked_mut(self.data_index) }
+    }
+
+    /// Replace value, returning the old one.
+    #[inline]
+    pub fn insert(&mut self, value: T) -> T {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Remove element from tree.
+    #[inline]
+    pub fn remove(self) -> T {
+        let indices = level_indices::<Mask, ConstUsize<DEPTH>>(self.index);
+        unsafe{
+            self.tree.remove_existing(
+                self.index, indices.as_ref(), &self.branch[..DEPTH-1], self.data_index
+            )
+        }
+    }
+}
+
+impl<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> VacantEntry<'a, T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    #[inline]
+    pub fn key(&self) -> usize {
+        self.index
+    }
+
+    /// Insert value. Missing nodes are created only now.
+    #[inline]
+    pub fn insert(self, value: T) -> &'a mut T {
+        let tree = self.tree;
+        // Before any node is created.
+        let data_index: I = to_data_index(tree.data.len());
+        let indices = level_indices::<Mask, ConstUsize<DEPTH>>(self.index);
+        unsafe{
+            let mut node: &mut NodePtr = match self.node_slot {
+                Some(slot) => &mut *slot.as_ptr(),
+                None => &mut tree.root,
+            };
+
+            for n in self.level..DEPTH-1 {
+                let inner_index = *indices.as_ref().get_unchecked(n);
+                let child = if n == DEPTH-2 /* child node is terminal */ {
+                    NodePtr::new::<I>(&mut tree.node_pool, node::DEFAULT_CAP, I::ZERO)
+                } else {
+                    // n + 2, because we point from child, and to it's child
+                    NodePtr::new::<NodePtr>(&mut tree.node_pool, node::DEFAULT_CAP, empty_node_at(n + 2, DEPTH))
+                };
+                // update a child pointer with a (possibly) new address
+                let (mut inserted_ptr, new_node) = node.insert(&mut tree.node_pool, inner_index, child);
+                *node = new_node;
+                node = inserted_ptr.as_mut();
+            }
+
+            let inner_index = *indices.as_ref().last().unwrap_unchecked();
+            tree.data.push(value);
+            tree.keys.push(self.index);
+            let (_, new_node) = node.insert(&mut tree.node_pool, inner_index, data_index);
+            *node = new_node;
+
+            tree.data.get_unchecked_mut(data_index.as_usize())
+        }
+    }
+}
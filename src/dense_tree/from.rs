@@ -3,12 +3,13 @@ use std::ops::ControlFlow::Continue;
 
 use crate::const_utils::{ConstInteger, ConstUsize};
 use crate::{
-    BitBlock, FromHibitTree, HibitTree, HibitTreeCursor,
+    BitBlock, FromHibitTree, NodeAllocator, HibitTree, HibitTreeCursor,
     HibitTreeCursorTypes, HibitTreeTypes
 };
 
 use super::node::{empty_node, NodePtr};
-use super::{DenseTree, DataIndex, Mask};
+use super::node_pool::NodePool;
+use super::{to_data_index, DenseTree, DataIndex, Mask};
 
 type CursorData<'src, 'state, L> = 
     <<L as HibitTreeTypes<'src>>
@@ -25,9 +26,10 @@ fn block_start<S: HibitTree, N: ConstInteger>(index: usize) -> usize {
 }
 
 #[inline]
-unsafe fn make_terminal_node<'src, L, F>(
+unsafe fn make_terminal_node<'src, L, F, I: DataIndex>(
     other: &'src L,
     other_cursor: &mut <L as HibitTreeTypes<'src>>::Cursor,
+    node_pool: &mut NodePool<impl NodeAllocator>,
     mask: Mask,
     cap: u8,
     key_acc: usize,
@@ -35,9 +37,9 @@ unsafe fn make_terminal_node<'src, L, F>(
 ) -> NodePtr
 where
     L: HibitTree<LevelMask = Mask>,
-    F: for<'a> FnMut(usize, CursorData<'src, 'a, L>) -> DataIndex
+    F: for<'a> FnMut(usize, CursorData<'src, 'a, L>) -> I
 {
-    let raw_node = NodePtr::raw_new::<DataIndex>(cap, mask);
+    let raw_node = NodePtr::raw_new::<I>(node_pool, cap, mask);
     mask.traverse_bits(|index| {
         let data = other_cursor.data_unchecked(other, index);
         let key = key_acc + index; 
@@ -46,13 +48,14 @@ where
         
         Continue(())
     });
-    return NodePtr::raw_finalize(raw_node, 0);    
+    return NodePtr::raw_finalize(raw_node, I::ZERO);    
 }
 
 #[inline(always)]
-unsafe fn from_exact_sparse_hierarchy<'src, L, N, F>(
+unsafe fn from_exact_sparse_hierarchy<'src, L, N, F, I: DataIndex>(
     other: &'src L,
     other_cursor: &mut <L as HibitTreeTypes<'src>>::Cursor,
+    node_pool: &mut NodePool<impl NodeAllocator>,
     n: N,
     index: usize,
     key_acc: usize,
@@ -60,7 +63,7 @@ unsafe fn from_exact_sparse_hierarchy<'src, L, N, F>(
 ) -> NodePtr
 where
     L: HibitTree<LevelMask = Mask>,
-    F: for<'a> FnMut(usize, CursorData<'src, 'a, L>) -> DataIndex,
+    F: for<'a> FnMut(usize, CursorData<'src, 'a, L>) -> I,
     N: ConstInteger,
 {
     assert!(L::EXACT_HIERARCHY);
@@ -71,16 +74,16 @@ where
     
     if N::VALUE == L::LevelCount::VALUE - 1 {
         // terminal node with data
-        return make_terminal_node(other, other_cursor, mask, cap, key_acc, push_data);
+        return make_terminal_node(other, other_cursor, node_pool, mask, cap, key_acc, push_data);
     }
     
-    let mut raw_node = NodePtr::raw_new::<NodePtr>(cap, mask);
+    let mut raw_node = NodePtr::raw_new::<NodePtr>(node_pool, cap, mask);
     mask.traverse_bits(|index| {
         // TODO: try calculate key the same in iter. Benchmark.
         // go deeper
         let key_acc = key_acc + block_start::<L, N>(index); 
         let child_node = from_exact_sparse_hierarchy(
-            other, other_cursor, n.inc(), index, key_acc, push_data
+            other, other_cursor, node_pool, n.inc(), index, key_acc, push_data
         );
         
         // connect to current
@@ -94,9 +97,10 @@ where
 }
 
 #[inline(always)]
-unsafe fn from_sparse_hierarchy<'src, L, N, F>(
+unsafe fn from_sparse_hierarchy<'src, L, N, F, I: DataIndex>(
     other: &'src L,
     other_cursor: &mut <L as HibitTreeTypes<'src>>::Cursor,
+    node_pool: &mut NodePool<impl NodeAllocator>,
     n: N,
     index: usize,
     key_acc: usize,
@@ -104,16 +108,19 @@ unsafe fn from_sparse_hierarchy<'src, L, N, F>(
 ) -> Option<NodePtr>
 where
     L: HibitTree<LevelMask = Mask>,
-    F: for<'a> FnMut(usize, CursorData<'src, 'a, L>) -> DataIndex,
+    F: for<'a> FnMut(usize, CursorData<'src, 'a, L>) -> I,
     N: ConstInteger,
 {
     let mask = other_cursor.select_level_node_unchecked(other, n, index);
     
     if N::VALUE == L::LevelCount::VALUE - 1 {
         // terminal node with data
+        if mask.is_zero() {
+            return None;
+        }
         let len = mask.count_ones() as u8;
         let cap = len + 1;
-        return Some(make_terminal_node(other, other_cursor, mask, cap, key_acc, push_data));        
+        return Some(make_terminal_node(other, other_cursor, node_pool, mask, cap, key_acc, push_data));        
     }
     
     let mut node_mask = Mask::zero();
@@ -121,7 +128,7 @@ where
     
     mask.traverse_bits(|index| {
         let key_acc = key_acc + block_start::<L, N>(index);
-        if let Some(child_node) = from_sparse_hierarchy(other, other_cursor, n.inc(), index, key_acc, push_data){
+        if let Some(child_node) = from_sparse_hierarchy(other, other_cursor, node_pool, n.inc(), index, key_acc, push_data){
             node_mask.set_bit::<true>(index);
             childs.push_unchecked(child_node);
         }
@@ -132,11 +139,11 @@ where
         None
     } else {
         let empty_child = empty_node(n.inc(), L::LevelCount::default());
-        Some(NodePtr::from_parts(node_mask, childs.as_slice(), empty_child))
+        Some(NodePtr::from_parts(node_pool, node_mask, childs.as_slice(), empty_child))
     }
 }
 
-impl<From, T, const DEPTH: usize> FromHibitTree<From> for DenseTree<T, DEPTH>
+impl<From, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator + Clone + Default> FromHibitTree<From> for DenseTree<T, DEPTH, I, A>
 where
     ConstUsize<DEPTH>: ConstInteger,
     From: HibitTree<
@@ -150,30 +157,35 @@ where
     >,    
 {
     fn from_sparse_hierarchy(other: From) -> Self {
-        let mut other_cursor = <From as HibitTreeTypes>::Cursor::new(&other);        
-        let mut data: Vec<T> = Vec::with_capacity(1);
-        unsafe{ data.set_len(1); }
-        
-        let mut keys = vec![usize::MAX];
+        let mut other_cursor = <From as HibitTreeTypes>::Cursor::new(&other);
         
-        let mut push_fn = |index, value| -> DataIndex {
-            let i = data.len(); 
+        // Elements pushed directly into `this`, so they are properly
+        // dropped, if something panics in between.
+        let mut this = Self::default();
+        let data = &mut this.data;
+        let keys = &mut this.keys;
+        let node_pool = &mut this.node_pool;
+        let mut push_fn = |index, value| -> I {
+            let i = to_data_index(data.len());
             data.push(value);
             keys.push(index);
-            i as DataIndex
+            i
         };
 
         let root = unsafe {
             if <From as HibitTree>::EXACT_HIERARCHY {
-                from_exact_sparse_hierarchy(
-                    &other, &mut other_cursor, ConstUsize::<0>, 0, 0, &mut push_fn
-                )
+                Some(from_exact_sparse_hierarchy(
+                    &other, &mut other_cursor, node_pool, ConstUsize::<0>, 0, 0, &mut push_fn
+                ))
             } else {
                 from_sparse_hierarchy(
-                    &other, &mut other_cursor, ConstUsize::<0>, 0, 0, &mut push_fn
-                ).unwrap_unchecked()
+                    &other, &mut other_cursor, node_pool, ConstUsize::<0>, 0, 0, &mut push_fn
+                )
             }
         };
-        Self{ root, data, keys }
+        if let Some(root) = root {
+            unsafe{ this.replace_root(root); }
+        }
+        this
     }
 }
\ No newline at end of file
@@ -0,0 +1,203 @@
+use std::array;
+use arrayvec::ArrayVec;
+
+use crate::const_utils::{ConstInteger, ConstUsize};
+use crate::{BitBlock, Index, NodeAllocator, level_indices};
+
+use super::node::{empty_node_at, NodePtr};
+use super::node_pool::NodePool;
+use super::{to_data_index, DenseTree, DataIndex, Mask};
+
+/// Builds node hierarchy from keys in ascending order.
+///
+/// Keeps one unfinished node per level - along the last pushed key path.
+/// Node gets allocated (with [NodePtr::from_parts]) only when key path leaves it,
+/// so each node built once, at its final capacity.
+///
+/// If dropped unfinished (on panic) - frees nodes built so far.
+struct SortedBuilder<'a, const DEPTH: usize, I: DataIndex, A: NodeAllocator>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    /// Intermediate levels. Last one unused.
+    nodes: [(Mask, ArrayVec<NodePtr, {Mask::SIZE}>); DEPTH],
+    terminal: (Mask, ArrayVec<I, {Mask::SIZE}>),
+    last_indices: Option<[usize; DEPTH]>,
+    node_pool: &'a mut NodePool<A>,
+}
+
+impl<'a, const DEPTH: usize, I: DataIndex, A: NodeAllocator> SortedBuilder<'a, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    #[inline]
+    fn new(node_pool: &'a mut NodePool<A>) -> Self {
+        Self{
+            nodes: array::from_fn(|_| Default::default()),
+            terminal: Default::default(),
+            last_indices: None,
+            node_pool,
+        }
+    }
+
+    /// Finish node at `level_n` and connect it to parent.
+    /// Returns node, if it was root.
+    #[inline]
+    unsafe fn close_node(&mut self, level_n: usize, last_indices: &[usize; DEPTH]) -> Option<NodePtr> {
+        let node = if level_n == DEPTH - 1 {
+            let (mask, childs) = &mut self.terminal;
+            let node = NodePtr::from_parts(self.node_pool, *mask, childs.as_slice(), I::ZERO);
+            *mask = Mask::zero();
+            childs.clear();
+            node
+        } else {
+            let (mask, childs) = &mut self.nodes[level_n];
+            let empty_child = empty_node_at(level_n + 1, DEPTH);
+            let node = NodePtr::from_parts(self.node_pool, *mask, childs.as_slice(), empty_child);
+            *mask = Mask::zero();
+            childs.clear();
+            node
+        };
+
+        if level_n == 0 {
+            return Some(node);
+        }
+        let (parent_mask, parent_childs) = &mut self.nodes[level_n - 1];
+        let inner_index = last_indices[level_n - 1];
+        parent_mask.set_bit::<true>(inner_index);
+        parent_childs.push_unchecked(node);
+        None
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is not greater than the previous one.
+    #[inline]
+    fn push(&mut self, index: usize, data_index: I) {
+        let indices = level_indices::<Mask, ConstUsize<DEPTH>>(index);
+        let indices: [usize; DEPTH] = array::from_fn(|n| indices.as_ref()[n]);
+
+        if let Some(last_indices) = self.last_indices {
+            assert!(last_indices < indices, "Keys must be in strictly ascending order.");
+
+            // Close all nodes below the level, where paths diverge.
+            let diverge_level = (0..DEPTH)
+                .position(|n| indices[n] != last_indices[n])
+                .unwrap();
+            for n in (diverge_level+1..DEPTH).rev() {
+                unsafe{ self.close_node(n, &last_indices); }
+            }
+        }
+
+        let (mask, childs) = &mut self.terminal;
+        mask.set_bit::<true>(indices[DEPTH - 1]);
+        unsafe{ childs.push_unchecked(data_index); }
+
+        self.last_indices = Some(indices);
+    }
+
+    /// Returns root node. `None` if nothing was pushed.
+    #[inline]
+    fn finish(mut self) -> Option<NodePtr> {
+        let last_indices = self.last_indices?;
+        for n in (1..DEPTH).rev() {
+            unsafe{ self.close_node(n, &last_indices); }
+        }
+        // All closed nodes are now in the root - nothing left to drop.
+        unsafe{ self.close_node(0, &last_indices) }
+    }
+
+    /// Free closed nodes, starting from level `N` children.
+    unsafe fn drop_closed_nodes<N: ConstInteger>(&mut self) {
+        if N::VALUE + 1 >= DEPTH {
+            return;
+        }
+        for child in self.nodes[N::VALUE].1.drain(..) {
+            child.drop_node_with_childs::<N::Inc, DEPTH, I>(self.node_pool);
+        }
+        self.drop_closed_nodes::<N::Inc>();
+    }
+}
+
+impl<'a, const DEPTH: usize, I: DataIndex, A: NodeAllocator> Drop for SortedBuilder<'a, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    #[inline]
+    fn drop(&mut self) {
+        // Terminal node children are data indices - there is nothing to free.
+        unsafe{ self.drop_closed_nodes::<ConstUsize<0>>(); }
+    }
+}
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator + Clone + Default> DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    /// Construct from `(index, value)` pairs, sorted by index.
+    ///
+    /// Unlike [FromIterator], which [insert]s elements one by one, each node
+    /// is allocated only once, at its final capacity.
+    ///
+    /// # Panics
+    ///
+    /// If indices are not in strictly ascending order, or out of [index_range].
+    ///
+    /// [insert]: Self::insert
+    /// [index_range]: crate::HibitTree::index_range
+    pub fn from_sorted_iter<It>(iter: It) -> Self
+    where
+        It: IntoIterator<Item = (usize, T)>
+    {
+        let iter = iter.into_iter();
+        let additional = iter.size_hint().0;
+        
+        // Elements pushed directly into `this`, so they are properly
+        // dropped, if something panics in between.
+        let mut this = Self::with_capacity(additional);
+
+        let mut builder = SortedBuilder::<DEPTH, I, A>::new(&mut this.node_pool);
+        for (index, value) in iter {
+            let index: usize = Index::<Mask, ConstUsize<DEPTH>>::from(index).into();
+            builder.push(index, to_data_index(this.data.len()));
+            this.data.push(value);
+            this.keys.push(index);
+        }
+        if let Some(root) = builder.finish() {
+            unsafe{ this.replace_root(root); }
+        }
+        this
+    }
+}
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator + Clone + Default> FromIterator<(usize, T)> for DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    /// Same as consecutive [insert]s. If your data is sorted -
+    /// use much faster [from_sorted_iter].
+    ///
+    /// [insert]: Self::insert
+    /// [from_sorted_iter]: Self::from_sorted_iter
+    #[inline]
+    fn from_iter<It: IntoIterator<Item = (usize, T)>>(iter: It) -> Self {
+        let mut this = Self::default();
+        this.extend(iter);
+        this
+    }
+}
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> Extend<(usize, T)> for DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    #[inline]
+    fn extend<It: IntoIterator<Item = (usize, T)>>(&mut self, iter: It) {
+        let iter = iter.into_iter();
+        let additional = iter.size_hint().0;
+        self.reserve(additional);
+        for (index, value) in iter {
+            self.insert(index, value);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::mem::size_of;
+
+use crate::const_utils::{ConstInteger, ConstUsize};
+use crate::{LevelMemoryStats, MemoryStats, NodeAllocator};
+
+use super::node::{EmptyNode, NodePtr};
+use super::{DenseTree, DataIndex, Mask};
+
+/// # Safety
+///
+/// `node` must be at level `N`.
+unsafe fn node_stats<N, const DEPTH: usize, I>(node: NodePtr, levels: &mut [LevelMemoryStats])
+where
+    N: ConstInteger,
+    I: DataIndex,
+{
+    let header = node.header();
+    let level = &mut levels[N::VALUE];
+    level.nodes += 1;
+    level.allocated_slots += header.capacity() as usize;
+    level.used_slots += header.mask().count_ones() as usize;
+    // mask + active_mask
+    level.mask_bytes += 2 * size_of::<Mask>();
+
+    if N::VALUE == DEPTH - 1 {
+        level.bytes += node.size::<I>();
+    } else {
+        level.bytes += node.size::<NodePtr>();
+        for child in node.children_iter::<NodePtr>() {
+            node_stats::<N::Inc, DEPTH, I>(*child, levels);
+        }
+    }
+}
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    /// Memory usage. Traverses all nodes.
+    ///
+    /// Node child slots include one trailing empty child.
+    #[inline]
+    pub fn memory_stats(&self) -> MemoryStats {
+        let mut levels = vec![LevelMemoryStats::default(); DEPTH];
+        unsafe{ node_stats::<ConstUsize<0>, DEPTH, I>(self.root, &mut levels); }
+        MemoryStats{
+            levels,
+            free_bytes: self.node_pool.pooled_bytes(),
+            // Root is never empty node.
+            sentinel_bytes: (DEPTH - 1) * size_of::<EmptyNode>(),
+            data_bytes: self.data.capacity() * size_of::<T>(),
+            keys_bytes: self.keys.capacity() * size_of::<usize>(),
+        }
+    }
+}
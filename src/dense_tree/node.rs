@@ -1,4 +1,4 @@
-use std::alloc::{alloc, dealloc, Layout, realloc};
+use std::alloc::Layout;
 use std::{mem, ptr};
 use std::mem::{align_of, MaybeUninit, size_of};
 use std::ops::ControlFlow;
@@ -9,14 +9,27 @@ use crate::bit_utils::{get_bit_unchecked, set_bit_unchecked};
 use crate::BitBlock;
 use crate::const_utils::ConstInteger;
 use super::DataIndex;
+use crate::NodeAllocator;
+use super::node_pool::NodePool;
 use super::Mask;
 
 pub(super) const DEFAULT_CAP: u8 = 2;
 
+/// Node switches to uncompressed mode, when children count exceeds this.
+const UNCOMPRESS_THRESHOLD: u32 = 32;
+
+/// Uncompressed node switches back to compressed mode, when children count
+/// drops below this. Lower than [UNCOMPRESS_THRESHOLD], so alternating 
+/// insert/remove around threshold does not switch modes back and forth.
+const COMPRESS_THRESHOLD: u32 = 16;
+
+/// Uncompressed node children count, with trailing empty child.
+const UNCOMPRESSED_LEN: u8 = Mask::BITS as u8 + 1;
+
 /// Just for safety
-pub(super) trait NodeChild: 'static{}
+pub(super) trait NodeChild: Copy + 'static{}
 impl NodeChild for NodePtr{}
-impl NodeChild for DataIndex{}
+impl<I: DataIndex> NodeChild for I{}
 
 fn empty_branch() -> &'static [EmptyNode] {
     macro_rules! gen_empty_branch {
@@ -24,6 +37,7 @@ fn empty_branch() -> &'static [EmptyNode] {
             static $name: [EmptyNode; $len] = [
                 $(
                     EmptyNode {
+                        active_mask: 0,
                         mask: 0,
                         capacity: 1,
                         len: 1,
@@ -31,6 +45,7 @@ fn empty_branch() -> &'static [EmptyNode] {
                     },           
                 )*
                 EmptyNode {
+                    active_mask: 0,
                     mask: 0,
                     capacity: 1,
                     len: 1,
@@ -45,13 +60,32 @@ fn empty_branch() -> &'static [EmptyNode] {
 
 #[inline]
 pub(super) fn empty_node<N: ConstInteger, DEPTH: ConstInteger>(_: N, _: DEPTH) -> NodePtr {
+    empty_node_at(N::VALUE, DEPTH::VALUE)
+}
+
+/// [empty_node] with runtime level.
+#[inline]
+pub(super) fn empty_node_at(level_n: usize, depth: usize) -> NodePtr {
     /*const*/ let empty_branch = empty_branch();
-    let ptr = &empty_branch[(empty_branch.len() - DEPTH::VALUE) + N::VALUE];
+    let ptr = &empty_branch[(empty_branch.len() - depth) + level_n];
     NodePtr(unsafe{ mem::transmute(ptr) })
 }
 
+/// Node have two modes:
+/// - Compressed - children stored densely, in `mask` bits order.
+/// - Uncompressed - each child stored at its own index, absent children are 
+///   empty child. Insert and remove are O(1).
+/// 
+/// Full node is the same in both modes.
+/// 
+/// See doc/node_compression.md.
 #[repr(C)]
 pub(super) struct NodeHeaderN<const N: usize> {
+    /// Used for children access. 
+    /// 
+    /// Equals `mask` in compressed mode, all ones in uncompressed.
+    active_mask: Mask,
+    
     mask: Mask,
     
     capacity: u8,
@@ -59,8 +93,8 @@ pub(super) struct NodeHeaderN<const N: usize> {
     
     /// NonNull<Node> / DataIndex
     /// 
-    /// Always have one element more than specified by mask. 
-    /// Last excess element = empty_node/0.
+    /// Always have one element more than specified by mask (or [Mask::BITS]
+    /// in uncompressed mode). Last excess element = empty_node/0.
     /// We need that, so get_dense_index(index) point to valid node, even
     /// if `index` points past the mask's bit population (popcnt).
     children_placeholder: [*const u8; N]
@@ -81,15 +115,25 @@ impl NodeHeader{
         &self.mask
     }
     
+    #[inline]
+    pub fn capacity(&self) -> u8 {
+        self.capacity
+    }
+    
+    #[inline]
+    pub fn is_uncompressed(&self) -> bool {
+        self.active_mask == Mask::MAX
+    }
+    
     /// `index` must be set. Otherwise, return unspecified number that is
     /// less or equal to mask population.
     #[inline]
     unsafe fn get_dense_index(&self, index: usize) -> usize {
         let block = if cfg!(target_feature = "bmi2") {
-            core::arch::x86_64::_bzhi_u64(self.mask, index as u32)
+            core::arch::x86_64::_bzhi_u64(self.active_mask, index as u32)
         } else {
             let mask = !(u64::MAX << index);
-            self.mask & mask
+            self.active_mask & mask
         };
         block.count_ones() as usize
     }    
@@ -189,11 +233,18 @@ impl NodePtr {
         }
     }
     
+    /// Allocated bytes.
+    #[inline]
+    pub fn size<T: NodeChild>(self) -> usize {
+        Self::layout::<T>(self.header().capacity).size()
+    }
+    
     #[inline]
-    pub fn new<T: NodeChild>(cap: u8, empty_child: T) -> Self {
+    pub fn new<T: NodeChild>(pool: &mut NodePool<impl NodeAllocator>, cap: u8, empty_child: T) -> Self {
         unsafe {
-            let node = alloc(Self::layout::<T>(cap)) as *mut NodeHeader;
+            let node = pool.alloc(Self::layout::<T>(cap)).as_ptr() as *mut NodeHeader;
             
+            addr_of_mut!((*node).active_mask).write(Mask::default());
             addr_of_mut!((*node).mask).write(Mask::default());
             debug_assert!(cap>=1);
             addr_of_mut!((*node).capacity).write(cap);
@@ -209,17 +260,20 @@ impl NodePtr {
     
     #[inline]
     pub unsafe fn from_parts<T: NodeChild>(
+        pool: &mut NodePool<impl NodeAllocator>,
         mask: Mask,
         childs: &[T],
         empty_child: T, 
     ) -> Self {
         let len = childs.len();
         let cap = (len + 1) as u8; 
-        let node = alloc(Self::layout::<T>(cap)) as *mut NodeHeader;
+        let node = pool.alloc(Self::layout::<T>(cap)).as_ptr() as *mut NodeHeader;
         
+        addr_of_mut!((*node).active_mask).write(mask);
         addr_of_mut!((*node).mask).write(mask);
         addr_of_mut!((*node).capacity).write(cap);
-        addr_of_mut!((*node).len).write(len as u8);
+        // + empty_child
+        addr_of_mut!((*node).len).write(cap);
         
         let mut this = Self(NonNull::new_unchecked(node));
         
@@ -237,10 +291,11 @@ impl NodePtr {
     }    
     
     #[inline]
-    pub fn raw_new<T: NodeChild>(cap: u8, mask: Mask) -> MaybeUninit<Self> {
+    pub fn raw_new<T: NodeChild>(pool: &mut NodePool<impl NodeAllocator>, cap: u8, mask: Mask) -> MaybeUninit<Self> {
         unsafe {
-            let node = alloc(Self::layout::<T>(cap)) as *mut NodeHeader;
+            let node = pool.alloc(Self::layout::<T>(cap)).as_ptr() as *mut NodeHeader;
             
+            addr_of_mut!((*node).active_mask).write(mask);
             addr_of_mut!((*node).mask).write(mask);
             addr_of_mut!((*node).capacity).write(cap);
             addr_of_mut!((*node).len).write(0);
@@ -295,29 +350,32 @@ impl NodePtr {
     /// - `T` must match stored data.
     /// - `index` must be in range.
     #[inline]
-    pub unsafe fn insert<T: NodeChild>(mut self, index: usize, value: T)
+    pub unsafe fn insert<T: NodeChild>(mut self, pool: &mut NodePool<impl NodeAllocator>, index: usize, value: T)
         // TODO: try Option
         -> (NonNull<T>, /*Option<*/Self/*>*/) 
     {
-        // TODO: special case for full-size mode
+        if !self.header().is_uncompressed() 
+            && self.header().mask.count_ones() >= UNCOMPRESS_THRESHOLD 
+        {
+            self = self.uncompress::<T>(pool);
+        }
         
-        /* realloc */ {         
-            let node = self.header();
-            let capacity = node.capacity;
-            if node.len == capacity {
-                let new_capacity = capacity * 2;
-                let new_ptr= realloc(
-                    self.0.as_ptr() as *mut u8,
-                    Self::layout::<T>(capacity),
-                    Self::layout::<T>(new_capacity).size(),
-                ) as *mut NodeHeader;
-                (*new_ptr).capacity = new_capacity; 
-                self.0 = NonNull::new_unchecked(new_ptr);
-            }
+        if self.header().is_uncompressed() {
+            set_bit_unchecked::<true, _>(&mut self.header_mut().mask, index);
+            // Overwrite empty child.
+            let p: *mut T = self.children_mut_ptr::<T>().add(index);
+            p.write(value);
+            return (NonNull::new_unchecked(p), self);
+        }
+        
+        let capacity = self.header().capacity;
+        if self.header().len == capacity {
+            self = self.realloc::<T>(pool, capacity * 2);
         }
         
         let header = self.header_mut();
         set_bit_unchecked::<true, _>(&mut header.mask, index);
+        header.active_mask = header.mask;
         let dense_index = header.get_dense_index(index);
         
         /* move right */ 
@@ -337,8 +395,20 @@ impl NodePtr {
     #[inline]
     pub unsafe fn remove<T: NodeChild>(mut self, index: usize){
         let header = self.header_mut();
+        if header.is_uncompressed() {
+            set_bit_unchecked::<false, _>(&mut header.mask, index);
+            let children = self.children_mut_ptr::<T>();
+            *children.add(index) = *children.add(Mask::BITS as usize);
+            
+            if header.mask.count_ones() < COMPRESS_THRESHOLD {
+                self.compress::<T>();
+            }
+            return;
+        }
+        
         let dense_index = header.get_dense_index(index);
-        set_bit_unchecked::<false, _>(&mut header.mask, index);        
+        set_bit_unchecked::<false, _>(&mut header.mask, index);
+        header.active_mask = header.mask;
 
         /* move left */
         let p: *mut _ = self.children_mut_ptr::<T>().add(dense_index);
@@ -347,28 +417,170 @@ impl NodePtr {
         header.len -= 1;
     }
     
+    /// Change capacity. Must fit `len`.
+    #[inline]
+    unsafe fn realloc<T: NodeChild>(mut self, pool: &mut NodePool<impl NodeAllocator>, new_capacity: u8) -> Self {
+        let capacity = self.header().capacity;
+        let new_ptr = pool.realloc(
+            self.0.cast(),
+            Self::layout::<T>(capacity),
+            Self::layout::<T>(new_capacity).size(),
+        ).as_ptr() as *mut NodeHeader;
+        (*new_ptr).capacity = new_capacity; 
+        self.0 = NonNull::new_unchecked(new_ptr);
+        self
+    }
+    
+    /// Switch to uncompressed mode. 
+    /// 
+    /// Returns a new pointer if relocation happened.
+    #[inline]
+    unsafe fn uncompress<T: NodeChild>(mut self, pool: &mut NodePool<impl NodeAllocator>) -> Self {
+        if self.header().capacity < UNCOMPRESSED_LEN {
+            self = self.realloc::<T>(pool, UNCOMPRESSED_LEN);
+        }
+        
+        let header = self.header_mut();
+        let children = self.children_mut_ptr::<T>();
+        let mut dense_index = header.len as usize - 1;
+        let empty_child = *children.add(dense_index);
+        
+        // From the end - children only move right, 
+        // so nothing overwritten before being moved.
+        for index in (0..Mask::BITS as usize).rev() {
+            if header.mask.get_bit(index) {
+                dense_index -= 1;
+                *children.add(index) = *children.add(dense_index);
+            } else {
+                *children.add(index) = empty_child;
+            }
+        }
+        *children.add(Mask::BITS as usize) = empty_child;
+        
+        header.active_mask = Mask::MAX;
+        header.len = UNCOMPRESSED_LEN;
+        self
+    }
+    
+    /// Switch back to compressed mode. Capacity unchanged.
+    #[inline]
+    unsafe fn compress<T: NodeChild>(self) {
+        let header = self.header_mut();
+        let children = self.children_mut_ptr::<T>();
+        let empty_child = *children.add(Mask::BITS as usize);
+        
+        // Children only move left.
+        let mut len = 0;
+        for index in header.mask.into_bits_iter() {
+            *children.add(len) = *children.add(index);
+            len += 1;
+        }
+        *children.add(len) = empty_child;
+        
+        header.active_mask = header.mask;
+        header.len = len as u8 + 1;
+    }
+    
+    /// Keep only children at `mask` bits, preserving order. Capacity unchanged.
+    /// 
+    /// # Safety
+    /// 
+    /// - `T` must match stored data.
+    /// - `mask` must be a subset of node mask.
+    #[inline]
+    pub unsafe fn compact<T: NodeChild>(mut self, mask: Mask) {
+        let header = self.header_mut();
+        let children = self.children_mut_ptr::<T>();
+        
+        if header.is_uncompressed() {
+            let empty_child = *children.add(Mask::BITS as usize);
+            for index in (header.mask & !mask).into_bits_iter() {
+                *children.add(index) = empty_child;
+            }
+            header.mask = mask;
+            
+            if mask.count_ones() < COMPRESS_THRESHOLD {
+                self.compress::<T>();
+            }
+            return;
+        }
+        
+        let mut len = 0;
+        for (dense_index, index) in header.mask.into_bits_iter().enumerate() {
+            if mask.get_bit(index) {
+                ptr::copy(children.add(dense_index), children.add(len), 1);
+                len += 1;
+            }
+        }
+        
+        // empty_child goes last
+        ptr::copy(children.add(header.len as usize - 1), children.add(len), 1);
+        
+        header.active_mask = mask;
+        header.mask = mask;
+        header.len = len as u8 + 1;
+    }
+    
     /// Deallocate node WITHOUT deallocating child objects.
+    /// 
+    /// Memory goes to `pool`.
     #[inline]
-    pub unsafe fn drop_node<T: NodeChild>(self){
+    pub unsafe fn drop_node<T: NodeChild>(self, pool: &mut NodePool<impl NodeAllocator>){
         let capacity = self.0.as_ref().capacity;
         let layout = Self::layout::<T>(capacity);
-        dealloc(self.0.as_ptr().cast(), layout);
+        pool.dealloc(self.0.cast(), layout);
     }
     
+    /// Allocate node copy with the same capacity. Children copied bitwise.
+    #[inline]
+    unsafe fn clone_node<T: NodeChild>(self, pool: &mut NodePool<impl NodeAllocator>) -> Self {
+        let header = self.header();
+        let node = pool.alloc(Self::layout::<T>(header.capacity)).as_ptr();
+
+        // header + initialized children
+        let size = Self::children_addr_offset() + size_of::<T>() * header.len as usize;
+        ptr::copy_nonoverlapping(self.0.as_ptr() as *const u8, node, size);
+
+        Self(NonNull::new_unchecked(node.cast()))
+    }
+
+    /// Deep copy of node hierarchy, as is.
+    ///
+    /// Terminal node children (data indices) copied verbatim.
+    #[inline]
+    pub unsafe fn clone_node_with_childs<
+        N: ConstInteger,
+        const LEVELS_COUNT: usize,
+        I: DataIndex
+    > (self, pool: &mut NodePool<impl NodeAllocator>) -> Self
+    {
+        /*const*/ if N::VALUE == LEVELS_COUNT - 1 {
+            self.clone_node::<I>(pool)
+        } else {
+            let node = self.clone_node::<NodePtr>(pool);
+            node.children_mut_iter()
+                .for_each(|child: &mut NodePtr|{
+                    *child = child.clone_node_with_childs::<N::Inc, LEVELS_COUNT, I>(pool)
+                });
+            node
+        }
+    }
+
     #[inline(always)]
     pub unsafe fn drop_node_with_childs<
         N: ConstInteger,
-        const LEVELS_COUNT: usize
-    > (mut self)
+        const LEVELS_COUNT: usize,
+        I: DataIndex
+    > (mut self, pool: &mut NodePool<impl NodeAllocator>)
     {
         /*const*/ if N::VALUE == LEVELS_COUNT - 1 {
-            self.drop_node::<DataIndex>();
+            self.drop_node::<I>(pool);
         } else {
             self.children_mut_iter()
                 .for_each(|child: &mut NodePtr|{
-                    child.drop_node_with_childs::<N::Inc, LEVELS_COUNT>()
+                    child.drop_node_with_childs::<N::Inc, LEVELS_COUNT, I>(pool)
                 });
-            self.drop_node::<NodePtr>();
+            self.drop_node::<NodePtr>(pool);
         }
     }
 }
@@ -0,0 +1,148 @@
+use std::alloc::Layout;
+use std::mem::{self, align_of, size_of};
+use std::ptr::{self, NonNull};
+
+use crate::NodeAllocator;
+use super::node::NodeHeader;
+
+const ALIGN: usize = align_of::<NodeHeader>();
+
+/// Deallocated node memory. Link to the next free node of the same size class.
+struct FreeNode {
+    next: Option<NonNull<FreeNode>>,
+}
+
+/// Free-lists of deallocated nodes, by size class.
+///
+/// Node layouts are padded to node alignment, so size class is just
+/// `size / align`. Nodes of different children type, but of the same size
+/// share the same free-list.
+///
+/// Pooled nodes returned to `allocator` only with [release()] or on drop.
+///
+/// [release()]: Self::release
+pub(super) struct NodePool<A: NodeAllocator> {
+    /// Free-list heads, indexed by size class. Grows on demand.
+    free_lists: Vec<Option<NonNull<FreeNode>>>,
+    allocator: A,
+}
+
+impl<A: NodeAllocator + Default> Default for NodePool<A> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(A::default())
+    }
+}
+
+impl<A: NodeAllocator> NodePool<A> {
+    #[inline]
+    pub fn new(allocator: A) -> Self {
+        Self{ free_lists: Vec::new(), allocator }
+    }
+    
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
+    
+    #[inline]
+    fn size_class(layout: Layout) -> usize {
+        debug_assert!(layout.align() == ALIGN);
+        debug_assert!(layout.size() % ALIGN == 0);
+        debug_assert!(layout.size() >= size_of::<FreeNode>());
+        layout.size() / ALIGN
+    }
+
+    #[inline]
+    fn pop(&mut self, size_class: usize) -> Option<NonNull<u8>> {
+        let head = self.free_lists.get_mut(size_class)?;
+        let node = (*head)?;
+        *head = unsafe{ node.as_ref().next };
+        Some(node.cast())
+    }
+
+    #[inline]
+    unsafe fn push(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        let size_class = Self::size_class(layout);
+        if self.free_lists.len() <= size_class {
+            self.free_lists.resize(size_class + 1, None);
+        }
+        let head = self.free_lists.get_unchecked_mut(size_class);
+        let node: NonNull<FreeNode> = ptr.cast();
+        node.as_ptr().write(FreeNode{ next: *head });
+        *head = Some(node);
+    }
+
+    /// Takes node from the pool, or allocates a new one.
+    #[inline]
+    pub fn alloc(&mut self, layout: Layout) -> NonNull<u8> {
+        if let Some(ptr) = self.pop(Self::size_class(layout)) {
+            return ptr;
+        }
+        self.allocator.allocate(layout)
+    }
+
+    /// Puts node into the pool.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be allocated with the same `layout`, and not used after.
+    #[inline]
+    pub unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        self.push(ptr, layout);
+    }
+
+    /// Moves node content into the pooled node of `new_size`, if any.
+    /// Otherwise, reallocates with `allocator`.
+    ///
+    /// # Safety
+    ///
+    /// Same as [NodeAllocator::reallocate].
+    #[inline]
+    pub unsafe fn realloc(&mut self, ptr: NonNull<u8>, layout: Layout, new_size: usize) -> NonNull<u8> {
+        let new_layout = Layout::from_size_align_unchecked(new_size, ALIGN);
+        if let Some(new_ptr) = self.pop(Self::size_class(new_layout)) {
+            ptr::copy_nonoverlapping(
+                ptr.as_ptr(),
+                new_ptr.as_ptr(),
+                layout.size().min(new_size)
+            );
+            self.push(ptr, layout);
+            return new_ptr;
+        }
+        self.allocator.reallocate(ptr, layout, new_size)
+    }
+
+    /// Bytes of pooled nodes.
+    pub fn pooled_bytes(&self) -> usize {
+        let mut bytes = 0;
+        for (size_class, mut head) in self.free_lists.iter().copied().enumerate() {
+            while let Some(node) = head {
+                bytes += size_class * ALIGN;
+                head = unsafe{ node.as_ref().next };
+            }
+        }
+        bytes
+    }
+
+    /// Returns all pooled nodes to `allocator`.
+    pub fn release(&mut self) {
+        let free_lists = mem::take(&mut self.free_lists);
+        for (size_class, mut head) in free_lists.into_iter().enumerate() {
+            while let Some(node) = head {
+                unsafe{
+                    head = node.as_ref().next;
+                    let layout = Layout::from_size_align_unchecked(size_class * ALIGN, ALIGN);
+                    self.allocator.deallocate(node.cast(), layout);
+                }
+            }
+        }
+    }
+}
+
+impl<A: NodeAllocator> Drop for NodePool<A> {
+    #[inline]
+    fn drop(&mut self) {
+        self.release();
+    }
+}
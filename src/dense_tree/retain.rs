@@ -0,0 +1,198 @@
+use std::{mem, panic, ptr, vec};
+use std::panic::AssertUnwindSafe;
+
+use crate::const_utils::{ConstInteger, ConstUsize};
+use crate::prune::{KeepAll, Prune, RemoveAll};
+use crate::alloc_vec::AllocVec;
+use crate::utils::Primitive;
+use crate::{BitBlock, NodeAllocator};
+
+use super::node::NodePtr;
+use super::{DenseTree, DataIndex, Mask};
+
+struct Retain<A: NodeAllocator, F, R> {
+    /// Elements are moved out of here, one by one.
+    old_data: *mut u8,
+    old_keys: AllocVec<usize, A>,
+
+    /// `true` - keep element.
+    f: F,
+    /// Receives removed element.
+    on_remove: R,
+}
+
+/// If retain's `f` panics - tree becomes empty.
+///
+/// Node hierarchy and not yet visited elements leaked.
+struct ClearOnPanic<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator>(&'a mut DenseTree<T, DEPTH, I, A>)
+where
+    ConstUsize<DEPTH>: ConstInteger;
+
+impl<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> Drop for ClearOnPanic<'a, T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    #[inline]
+    fn drop(&mut self) {
+        let tree = &mut *self.0;
+        tree.root = DenseTree::<T, DEPTH, I, A>::new_root(&mut tree.node_pool);
+        tree.keys.truncate(1);
+        tree.data.truncate(1);
+    }
+}
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator + Clone> DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// Elements visited in key order. Single pass over the tree.
+    /// `data` rebuilt in key order, and empty nodes are removed.
+    ///
+    /// If `f` panics, tree is left empty, and not yet visited elements are leaked.
+    #[inline]
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize, &mut T) -> bool
+    {
+        self.retain_impl(|key, value: &mut T| f(key, value), |_, value| drop(value), &mut KeepAll);
+    }
+
+    /// Removes all elements matching `pred`, and returns them in key order.
+    ///
+    /// Unlike [Vec::extract_if], this is eager - all matching elements are removed
+    /// immediately, in a single [retain]-like pass.
+    ///
+    /// If `pred` panics, the pass still completes, and then panic resumes.
+    /// Elements extracted before the panic are dropped. The rest, including
+    /// the one `pred` panicked on, stay in the tree.
+    ///
+    /// [retain]: Self::retain
+    #[inline]
+    pub fn extract_if<F>(&mut self, mut pred: F) -> vec::IntoIter<(usize, T)>
+    where
+        F: FnMut(usize, &mut T) -> bool
+    {
+        let mut out = Vec::new();
+        let mut panic = None;
+        self.retain_impl(
+            |key, value: &mut T| {
+                // After `pred` panicked - keep the rest, to finish the pass.
+                if panic.is_some() {
+                    return true;
+                }
+                match panic::catch_unwind(AssertUnwindSafe(|| pred(key, value))) {
+                    Ok(extract) => !extract,
+                    Err(payload) => {
+                        panic = Some(payload);
+                        true
+                    }
+                }
+            },
+            |key, value| out.push((key, value)),
+            &mut KeepAll
+        );
+        if let Some(payload) = panic {
+            panic::resume_unwind(payload);
+        }
+        out.into_iter()
+    }
+
+    /// `prune` removes whole subtrees, before `f` get called.
+    pub(super) fn retain_impl<F, R, P>(&mut self, f: F, on_remove: R, prune: &mut P)
+    where
+        F: FnMut(usize, &mut T) -> bool,
+        R: FnMut(usize, T),
+        P: Prune<Mask>,
+    {
+        let len = self.len();
+        let allocator = self.allocator().clone();
+        let mut old_data = mem::replace(&mut self.data, unsafe{ AllocVec::with_placeholder_in(len + 1, allocator.clone()) });
+        let old_keys = mem::replace(&mut self.keys, {
+            let mut keys = AllocVec::with_capacity_in(len + 1, allocator);
+            keys.push(usize::MAX);
+            keys
+        });
+        // Values now owned by `Retain`. Dropping `old_data` will only deallocate.
+        unsafe{ old_data.set_len(0); }
+
+        let mut ctx = Retain{
+            old_data: old_data.as_mut_ptr().cast(),
+            old_keys,
+            f,
+            on_remove
+        };
+
+        let guard = ClearOnPanic(self);
+        unsafe{
+            retain_node(guard.0, guard.0.root, ConstUsize::<0>, 0, &mut ctx, prune);
+        }
+        mem::forget(guard);
+    }
+}
+
+/// Returns true if node become empty.
+///
+/// `level_index` - `node` index in parent.
+unsafe fn retain_node<T, F, R, P, N, const DEPTH: usize, I: DataIndex, A: NodeAllocator>(
+    tree: &mut DenseTree<T, DEPTH, I, A>,
+    node: NodePtr,
+    n: N,
+    level_index: usize,
+    ctx: &mut Retain<A, F, R>,
+    prune: &mut P,
+) -> bool
+where
+    ConstUsize<DEPTH>: ConstInteger,
+    F: FnMut(usize, &mut T) -> bool,
+    R: FnMut(usize, T),
+    P: Prune<Mask>,
+    N: ConstInteger
+{
+    let mask = *node.header().mask();
+    let (keep, descend) = prune.enter(n, level_index, mask);
+    let mut new_mask = mask;
+
+    if N::VALUE == DEPTH - 1 {
+        for (index, data_index) in mask.into_bits_iter().zip(node.children_mut_iter::<I>()) {
+            let old_index = data_index.as_usize();
+            let key = *ctx.old_keys.get_unchecked(old_index);
+            let value = &mut *ctx.old_data.cast::<T>().add(old_index);
+            if keep.get_bit(index) && (ctx.f)(key, value) {
+                *data_index = I::from_usize(tree.data.len());
+                tree.data.push(ptr::read(value));
+                tree.keys.push(key);
+            } else {
+                new_mask.set_bit::<false>(index);
+                (ctx.on_remove)(key, ptr::read(value));
+            }
+        }
+        if new_mask != mask {
+            node.compact::<I>(new_mask);
+        }
+    } else {
+        for (index, child) in mask.into_bits_iter().zip(node.children_mut_iter::<NodePtr>()) {
+            let empty = if !keep.get_bit(index) {
+                retain_node(tree, *child, n.inc(), index, ctx, &mut RemoveAll)
+            } else if descend.get_bit(index) {
+                retain_node(tree, *child, n.inc(), index, ctx, prune)
+            } else {
+                retain_node(tree, *child, n.inc(), index, ctx, &mut KeepAll)
+            };
+            if empty {
+                new_mask.set_bit::<false>(index);
+                if N::VALUE == DEPTH - 2 {
+                    child.drop_node::<I>(&mut tree.node_pool);
+                } else {
+                    child.drop_node::<NodePtr>(&mut tree.node_pool);
+                }
+            }
+        }
+        if new_mask != mask {
+            node.compact::<NodePtr>(new_mask);
+        }
+    }
+
+    new_mask.is_zero()
+}
@@ -0,0 +1,90 @@
+use std::{mem, ptr};
+
+use crate::const_utils::{ConstInteger, ConstUsize};
+use crate::prune::{Intersect, Subtract};
+use crate::{HibitTree, HibitTreeData, NodeAllocator, RegularHibitTree};
+
+use super::entry::{Entry, OccupiedEntry};
+use super::{DataIndex, DenseTree, Mask};
+
+/// Removes element, which value was moved out, if merge panics.
+struct RemoveOnPanic<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator>(Option<OccupiedEntry<'a, T, DEPTH, I, A>>)
+where
+    ConstUsize<DEPTH>: ConstInteger;
+
+impl<'a, T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> Drop for RemoveOnPanic<'a, T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(entry) = self.0.take() {
+            // Value already moved out.
+            mem::forget(entry.remove());
+        }
+    }
+}
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator + Clone> DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    /// Keep only elements with indices in `other`.
+    ///
+    /// Subtrees not in `other` removed as a whole, by node masks.
+    /// Same as [retain], `data` rebuilt in key order.
+    ///
+    /// [retain]: Self::retain
+    #[inline]
+    pub fn intersect_with<O>(&mut self, other: &O)
+    where
+        O: HibitTree<LevelMask = Mask, LevelCount = ConstUsize<DEPTH>>
+    {
+        self.retain_impl(|_, _: &mut T| true, |_, value| drop(value), &mut Intersect::new(other));
+    }
+
+    /// Remove elements with indices in `other`.
+    ///
+    /// Only subtrees, that are in `other`, visited element by element.
+    /// Same as [retain], `data` rebuilt in key order.
+    ///
+    /// [retain]: Self::retain
+    #[inline]
+    pub fn difference_with<O>(&mut self, other: &O)
+    where
+        O: HibitTree<LevelMask = Mask, LevelCount = ConstUsize<DEPTH>>
+    {
+        self.retain_impl(|_, _: &mut T| true, |_, value| drop(value), &mut Subtract::new(other));
+    }
+
+    /// Merge all `other` elements into `self`.
+    ///
+    /// `f(Option<T>, other_data) -> T` receives existing element if any,
+    /// and returns a new one.
+    ///
+    /// Existing element is merged in place, with a single tree lookup per
+    /// `other` element. If `f` panics while merging, that element is removed.
+    ///
+    /// See [crate::union_with] for lazy union.
+    #[inline]
+    pub fn union_with<'a, O, F>(&mut self, other: &'a O, mut f: F)
+    where
+        O: RegularHibitTree<LevelMask = Mask, LevelCount = ConstUsize<DEPTH>>,
+        F: FnMut(Option<T>, HibitTreeData<'a, O>) -> T
+    {
+        for (index, data) in other.iter() {
+            match self.entry(index) {
+                Entry::Occupied(e) => {
+                    let mut guard = RemoveOnPanic(Some(e));
+                    unsafe{
+                        let slot: *mut T = guard.0.as_mut().unwrap_unchecked().get_mut();
+                        let value = ptr::read(slot);
+                        ptr::write(slot, f(Some(value), data));
+                    }
+                    guard.0 = None;
+                }
+                Entry::Vacant(e) => { e.insert(f(None, data)); }
+            }
+        }
+    }
+}
@@ -82,4 +82,516 @@ fn test_exact_from(){
         [(15, &15), (4500, &4500)]
     );
 }
-*/
\ No newline at end of file
+*/
+
+#[test]
+fn test_clone(){
+    let mut a: DenseTree<String, 3> = Default::default();
+    for i in [15, 4500, 4501, 200_000] {
+        a.insert(i, i.to_string());
+    }
+    
+    let mut b = a.clone();
+    assert_eq!(a, b);
+    assert_equal(b.iter(), a.iter());
+    
+    // Clone must be independent
+    b.remove(4500);
+    b.insert(16, "16".to_string());
+    assert_ne!(a, b);
+    assert_equal(a.iter().map(|(k, _)| k), [15, 4500, 4501, 200_000]);
+    assert_equal(b.iter().map(|(k, _)| k), [15, 16, 4501, 200_000]);
+}
+
+#[test]
+fn test_eq_hash_debug(){
+    use std::hash::{BuildHasher, RandomState};
+    
+    let mut a: DenseTree<usize, 2> = Default::default();
+    let mut b: DenseTree<usize, 2> = Default::default();
+    for i in [1, 100, 200] {
+        a.insert(i, i);
+    }
+    // different insertion history
+    for i in [200, 1, 5, 100] {
+        b.insert(i, i);
+    }
+    assert_ne!(a, b);
+    b.remove(5);
+    assert_ne!(a.key_values(), b.key_values());
+    assert_eq!(a, b);
+    
+    let s = RandomState::new();
+    assert_eq!(s.hash_one(&a), s.hash_one(&b));
+    
+    assert_eq!(format!("{a:?}"), "{1: 1, 100: 100, 200: 200}");
+    
+    b.insert(100, 0);
+    assert_ne!(a, b);
+}
+
+#[test]
+fn test_len_clear_contains(){
+    let mut a: DenseTree<String, 3> = Default::default();
+    assert!(a.is_empty());
+    assert!(HibitTree::is_empty(&a));
+    
+    for i in [1, 15, 300, 70_000] {
+        a.insert(i, i.to_string());
+    }
+    assert_eq!(a.len(), 4);
+    assert!(!a.is_empty());
+    assert!(!HibitTree::is_empty(&a));
+    assert!(a.contains(300));
+    assert!(!a.contains(301));
+    assert!(!a.contains(200_000));
+    
+    a.remove(300);
+    assert_eq!(a.len(), 3);
+    assert!(!a.contains(300));
+    
+    a.clear();
+    assert_eq!(a.len(), 0);
+    assert!(a.is_empty());
+    assert!(HibitTree::is_empty(&a));
+    assert!(!a.contains(15));
+    assert_eq!(a.iter().count(), 0);
+    
+    // still usable
+    a.insert(15, "15".to_string());
+    assert_equal(a.iter(), [(15, &"15".to_string())]);
+}
+
+#[test]
+fn test_from_iter(){
+    use rand::{Rng, SeedableRng};
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0xe15bb9db3dee3a0f);
+    
+    #[cfg(not(miri))]
+    const COUNT: usize = 10_000;
+    #[cfg(miri)]
+    const COUNT: usize = 100;
+    
+    let mut keys: Vec<usize> = (0..COUNT).map(|_| rng.gen_range(0..260_000)).collect();
+    keys.sort();
+    keys.dedup();
+    
+    let sorted: DenseTree<usize, 3> = DenseTree::from_sorted_iter(keys.iter().map(|&k| (k, k)));
+    let collected: DenseTree<usize, 3> = keys.iter().rev().map(|&k| (k, k)).collect();
+    assert_eq!(sorted.len(), keys.len());
+    assert_eq!(sorted, collected);
+    assert_equal(sorted.iter().map(|(k, v)| (k, *v)), keys.iter().map(|&k| (k, k)));
+    
+    // Nodes made by bulk build must stay usable.
+    let mut a = sorted.clone();
+    let mut b = collected;
+    for _ in 0..COUNT/2 {
+        let k = rng.gen_range(0..260_000);
+        if rng.gen_bool(0.5) {
+            a.insert(k, k);
+            b.insert(k, k);
+        } else {
+            assert_eq!(a.remove(k), b.remove(k));
+        }
+    }
+    assert_eq!(a, b);
+    
+    b.extend(keys.iter().map(|&k| (k, k+1)));
+    assert!(keys.iter().all(|&k| b.get(k) == Some(&(k+1))));
+}
+
+#[test]
+fn test_from_sorted_iter_edge(){
+    let empty: DenseTree<usize, 2> = DenseTree::from_sorted_iter([]);
+    assert!(empty.is_empty());
+    
+    let mut a: DenseTree<usize, 1> = DenseTree::from_sorted_iter([(1, 1), (63, 63)]);
+    a.insert(2, 2);
+    assert_equal(a.iter(), [(1, &1), (2, &2), (63, &63)]);
+    
+    // Full node
+    let a: DenseTree<usize, 2> = DenseTree::from_sorted_iter((0..4096).map(|k| (k, k)));
+    assert_eq!(a.len(), 4096);
+    assert_equal(a.iter().map(|(k, _)| k), 0..4096);
+}
+
+#[test]
+#[should_panic]
+fn test_from_sorted_iter_unsorted(){
+    let _: DenseTree<usize, 2> = DenseTree::from_sorted_iter([(1, 1), (3, 3), (2, 2)]);
+}
+
+#[test]
+fn test_from_sorted_iter_unsorted_drop(){
+    use std::panic::catch_unwind;
+    use std::rc::Rc;
+    let counter = Rc::new(());
+    let r = catch_unwind(|| {
+        let items = (0..1000).chain([5]).map(|k| (k * 37, counter.clone()));
+        let _: DenseTree<Rc<()>, 3> = DenseTree::from_sorted_iter(items);
+    });
+    assert!(r.is_err());
+    // Already pushed elements dropped once.
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+#[test]
+fn test_entry(){
+    use super::Entry;
+    let mut a: DenseTree<usize, 3> = Default::default();
+    
+    *a.entry(15).or_insert(0) += 1;
+    *a.entry(15).or_insert(0) += 1;
+    *a.entry(70_000).or_default() += 5;
+    a.entry(16).and_modify(|v| *v = 100).or_insert_with(|| 16);
+    a.entry(15).and_modify(|v| *v += 10).or_insert_with_key(|k| k);
+    assert_equal(a.iter(), [(15, &12), (16, &16), (70_000, &5)]);
+    
+    match a.entry(16) {
+        Entry::Occupied(mut e) => {
+            assert_eq!(e.key(), 16);
+            assert_eq!(e.insert(17), 16);
+            assert_eq!(e.get(), &17);
+            assert_eq!(e.remove(), 17);
+        }
+        Entry::Vacant(_) => unreachable!()
+    }
+    match a.entry(70_000) {
+        Entry::Occupied(e) => assert_eq!(e.remove(), 5),
+        Entry::Vacant(_) => unreachable!()
+    }
+    match a.entry(70_001) {
+        Entry::Vacant(e) => {
+            assert_eq!(e.key(), 70_001);
+            *e.insert(1) += 1;
+        }
+        Entry::Occupied(_) => unreachable!()
+    }
+    assert_equal(a.iter(), [(15, &12), (70_001, &2)]);
+    assert_eq!(a.len(), 2);
+}
+
+#[test]
+fn test_depth1(){
+    let mut a: DenseTree<usize, 1> = Default::default();
+    a.insert(1, 1);
+    a.insert(5, 5);
+    assert_eq!(a.remove(1), Some(1));
+    assert_eq!(a.remove(1), None);
+    assert_eq!(a.remove(5), Some(5));
+    assert!(a.is_empty());
+    *a.entry(3).or_default() += 3;
+    assert_equal(a.iter(), [(3, &3)]);
+}
+
+#[test]
+fn test_retain(){
+    use rand::{Rng, SeedableRng};
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0xe15bb9db3dad3a2e);
+    let mut keys: Vec<usize> = (0..4000).map(|_| rng.gen_range(0..262_144)).collect();
+    keys.sort();
+    keys.dedup();
+    
+    let mut a: DenseTree<usize, 3> = keys.iter().rev().map(|&k| (k, k)).collect();
+    a.retain(|k, v| { *v += 1; k % 3 != 0 });
+    let expected: Vec<_> = keys.iter().copied().filter(|k| k % 3 != 0).collect();
+    assert_equal(a.iter().map(|(k, v)| (k, *v)), expected.iter().map(|&k| (k, k + 1)));
+    assert_eq!(a.len(), expected.len());
+    
+    let extracted: Vec<_> = a.extract_if(|k, _| k < 100_000).collect();
+    assert_equal(extracted, expected.iter().copied().filter(|&k| k < 100_000).map(|k| (k, k + 1)));
+    let expected: Vec<_> = expected.into_iter().filter(|&k| k >= 100_000).collect();
+    assert_equal(a.iter().map(|(k, _)| k), expected.iter().copied());
+    
+    // Tree stays usable.
+    for &k in expected.iter().step_by(2) {
+        assert_eq!(a.remove(k), Some(k + 1));
+    }
+    a.insert(5, 5);
+    assert_equal(a.iter().map(|(k, _)| k), [5].into_iter().chain(expected.iter().copied().skip(1).step_by(2)));
+    
+    a.retain(|_, _| false);
+    assert!(a.is_empty());
+    assert!(a.iter().next().is_none());
+    a.insert(5, 5);
+    assert_equal(a.iter(), [(5, &5)]);
+    
+    let mut b: DenseTree<usize, 1> = (0..10).map(|k| (k, k)).collect();
+    b.retain(|k, _| k % 2 == 0);
+    assert_equal(b.iter().map(|(k, _)| k), [0, 2, 4, 6, 8]);
+    
+    // Panicking `pred` keeps not yet visited elements.
+    let mut c: DenseTree<usize, 2> = (0..100).map(|k| (k, k)).collect();
+    let r = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        c.extract_if(|k, _| if k == 50 { panic!() } else { k % 2 == 0 }).count()
+    }));
+    assert!(r.is_err());
+    assert_equal(c.iter().map(|(k, _)| k), (0..100).filter(|&k| k % 2 != 0 || k >= 50));
+    assert_eq!(c.len(), 75);
+}
+
+#[test]
+fn test_set_ops(){
+    use crate::ops::intersection::intersection;
+    type Tree = DenseTree<usize, 3>;
+    let a: Tree = (0..5000).map(|k| (k, k)).collect();
+    let by2: Tree = (0..5000).step_by(2).map(|k| (k, k)).collect();
+    let by3: Tree = (0..5000).step_by(3).map(|k| (k, k)).collect();
+    let far: Tree = [(100_000, 100_000)].into_iter().collect();
+
+    let mut t = a.clone();
+    t.intersect_with(&by2);
+    assert_equal(t.iter().map(|(k, v)| (k, *v)), (0..5000).step_by(2).map(|k| (k, k)));
+    assert_eq!(t.len(), 2500);
+
+    // Non-exact hierarchy.
+    t.intersect_with(&intersection(&by3, &by3));
+    assert_equal(t.iter().map(|(k, _)| k), (0..5000).step_by(6));
+    t.intersect_with(&far);
+    assert!(t.is_empty());
+
+    let mut t = a.clone();
+    t.difference_with(&intersection(&by2, &by3));
+    assert_equal(t.iter().map(|(k, _)| k), (0..5000).filter(|k| k % 6 != 0));
+    t.difference_with(&a);
+    assert!(t.is_empty());
+    t.insert(7, 7);
+    assert_equal(t.iter(), [(7, &7)]);
+
+    let mut t = by2.clone();
+    t.union_with(&by3, |v: Option<usize>, d: &usize| v.unwrap_or(0) + d);
+    assert_equal(
+        t.iter().map(|(k, v)| (k, *v)),
+        (0..5000).filter(|k| k % 2 == 0 || k % 3 == 0).map(|k| (k, if k % 6 == 0 { 2 * k } else { k }))
+    );
+    // Tree stays usable.
+    assert_eq!(t.remove(6), Some(12));
+    assert_eq!(t.remove(9), Some(9));
+    assert_eq!(t.get(8), Some(&8));
+
+    // Panic loses only the merged element.
+    let mut t = by2.clone();
+    let r = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        t.union_with(&by3, |v: Option<usize>, d: &usize| {
+            assert!(v != Some(12));
+            v.unwrap_or(0) + d
+        });
+    }));
+    assert!(r.is_err());
+    assert_eq!(t.get(6), Some(&12));
+    assert_eq!(t.get(12), None);
+    assert_eq!(t.get(14), Some(&14));
+    assert_eq!(t.get(15), None);
+}
+
+#[test]
+fn test_pop(){
+    let mut a: DenseTree<usize, 3> = [(5, 5), (70_000, 70_000), (100, 100), (3, 3)].into_iter().collect();
+    assert_eq!(a.first_key_value(), Some((3, &3)));
+    assert_eq!(a.last_key_value(), Some((70_000, &70_000)));
+    
+    assert_eq!(a.pop_first(), Some((3, 3)));
+    assert_eq!(a.pop_last(), Some((70_000, 70_000)));
+    assert_eq!(a.pop_last(), Some((100, 100)));
+    assert_equal(a.iter(), [(5, &5)]);
+    assert_eq!(a.pop_first(), Some((5, 5)));
+    assert_eq!(a.pop_first(), None);
+    assert_eq!(a.pop_last(), None);
+    assert!(a.is_empty());
+    
+    // Priority queue
+    let mut a: DenseTree<usize, 2> = (0..4096).rev().step_by(7).map(|k| (k, k)).collect();
+    let mut popped = Vec::new();
+    while let Some((k, _)) = a.pop_first() {
+        popped.push(k);
+    }
+    assert_equal(popped.into_iter().rev(), (0..4096).rev().step_by(7));
+    assert!(a.iter().next().is_none());
+}
+
+#[test]
+fn test_uncompressed_nodes(){
+    use std::collections::BTreeMap;
+    use rand::{Rng, SeedableRng};
+    
+    type Tree = DenseTree<usize, 2>;
+    let mut a = Tree::default();
+    let mut expected = BTreeMap::new();
+    let check = |a: &Tree, expected: &BTreeMap<usize, usize>| {
+        assert_equal(a.iter().map(|(k, v)| (k, *v)), expected.iter().map(|(k, v)| (*k, *v)));
+        for k in 0..4096 {
+            assert_eq!(a.get(k), expected.get(&k));
+        }
+    };
+    
+    // Root and first terminal node switch up.
+    for k in (0..40).chain((1..40).map(|i| i * 64)) {
+        a.insert(k, k);
+        expected.insert(k, k);
+    }
+    assert!(a.root.header().is_uncompressed());
+    check(&a, &expected);
+    
+    // Not switched down, until below lower threshold.
+    for k in (0..40).step_by(2).chain((1..30).map(|i| i * 64)) {
+        assert_eq!(a.remove(k), expected.remove(&k));
+    }
+    assert!(!a.root.header().is_uncompressed());
+    check(&a, &expected);
+    
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0x3a0fe15bb9db3dee);
+    for _ in 0..20_000 {
+        let k = rng.gen_range(0..3072);
+        if rng.gen_bool(0.5) {
+            a.insert(k, k + 1);
+            expected.insert(k, k + 1);
+        } else {
+            assert_eq!(a.remove(k), expected.remove(&k));
+        }
+    }
+    check(&a, &expected);
+    check(&a.clone(), &expected);
+    
+    a.retain(|k, _| k % 3 != 0);
+    expected.retain(|k, _| k % 3 != 0);
+    check(&a, &expected);
+    a.retain(|k, _| k % 5 == 0);
+    expected.retain(|k, _| k % 5 == 0);
+    check(&a, &expected);
+    
+    while let Some((k, v)) = a.pop_first() {
+        assert_eq!(expected.pop_first(), Some((k, v)));
+    }
+    assert!(a.is_empty());
+    assert!(!a.root.header().is_uncompressed());
+}
+
+#[test]
+fn test_data_index(){
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+    
+    let mut a: DenseTree<usize, 2, u8> = (0..255).map(|k| (k * 3, k)).collect();
+    assert_equal(a.iter().map(|(k, v)| (k, *v)), (0..255).map(|k| (k * 3, k)));
+    assert_eq!(a.remove(3), Some(1));
+    a.insert(1, 1);
+    assert_eq!(a.len(), 255);
+    
+    // Tree stays intact after overflow.
+    let r = catch_unwind(AssertUnwindSafe(|| a.insert(2, 2)));
+    assert!(r.is_err());
+    assert_eq!(a.len(), 255);
+    assert_eq!(a.get(2), None);
+    assert_eq!(a.iter().count(), 255);
+    assert_eq!(a.remove(1), Some(1));
+    a.insert(2, 2);
+    assert_eq!(a.get(2), Some(&2));
+    
+    // Already pushed elements dropped exactly once.
+    let counter = Rc::new(());
+    let r = catch_unwind(AssertUnwindSafe(|| {
+        DenseTree::<Rc<()>, 2, u8>::from_sorted_iter((0..300).map(|k| (k, counter.clone())))
+    }));
+    assert!(r.is_err());
+    assert_eq!(Rc::strong_count(&counter), 1);
+    
+    let src: DenseTree<Rc<()>, 2> = (0..300).map(|k| (k, counter.clone())).collect();
+    let r = catch_unwind(AssertUnwindSafe(|| {
+        let _: DenseTree<Rc<()>, 2, u8> = crate::LazyHibitTree::materialize(crate::map(&src, |v: &Rc<()>| v.clone()));
+    }));
+    assert!(r.is_err());
+    assert_eq!(Rc::strong_count(&counter), 301);
+}
+
+#[test]
+#[should_panic(expected = "DenseTree elements count exceeds `u8` data index capacity")]
+fn test_data_index_overflow(){
+    let _: DenseTree<usize, 2, u8> = (0..256).map(|k| (k, k)).collect();
+}
+
+#[test]
+fn test_capacity_and_node_pool(){
+    use super::node::NodePtr;
+    
+    type Tree = DenseTree<usize, 2>;
+    let terminal_node = |a: &Tree, i: usize| unsafe{ *a.root.get_child::<NodePtr>(i) };
+    
+    let mut a = Tree::with_capacity(100);
+    assert!(a.capacity() >= 100);
+    a.reserve(200);
+    assert!(a.capacity() >= 200);
+    
+    // Removed node reused by insert.
+    a.insert(0, 0);
+    a.insert(5 * 64, 1);
+    let node = terminal_node(&a, 5);
+    assert_eq!(a.remove(5 * 64), Some(1));
+    a.insert(7 * 64, 2);
+    assert_eq!(terminal_node(&a, 7).header() as *const _, node.header() as *const _);
+    
+    // Grown by realloc nodes too.
+    for k in 0..200 {
+        a.insert(k, k);
+    }
+    for k in 0..200 {
+        assert_eq!(a.remove(k), Some(k));
+    }
+    for k in 0..200 {
+        a.insert(k + 64, k);
+    }
+    assert_equal(a.iter().map(|(k, v)| (k, *v)), (0..200).map(|k| (k + 64, k)).chain([(7 * 64, 2)]));
+    
+    a.clear();
+    a.shrink_to_fit();
+    assert_eq!(a.capacity(), 0);
+    assert!(a.is_empty());
+    
+    a.extend((0..100).map(|k| (k * 3, k)));
+    let b = a.clone();
+    a.shrink_to_fit();
+    assert_eq!(a.capacity(), 100);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_memory_stats(){
+    use std::mem::size_of;
+    use crate::LevelMemoryStats;
+    use super::Mask;
+    use super::node::{EmptyNode, NodeHeader, NodePtr};
+    
+    // Node with `capacity` children of `child_size` bytes each.
+    let node_bytes = |capacity: usize, child_size: usize| size_of::<NodeHeader>() + capacity * child_size;
+    let node_mask_bytes = 2 * size_of::<Mask>();
+    let ptr_size = size_of::<NodePtr>();
+    let index_size = size_of::<u32>();
+    
+    let mut a: DenseTree<usize, 2> = Default::default();
+    let stats = a.memory_stats();
+    assert_eq!(stats.levels, [
+        LevelMemoryStats{ nodes: 1, allocated_slots: 2, used_slots: 0, mask_bytes: node_mask_bytes, bytes: node_bytes(2, ptr_size) },
+        LevelMemoryStats::default(),
+    ]);
+    assert_eq!(stats.sentinel_bytes, size_of::<EmptyNode>());
+    assert_eq!(stats.free_bytes, 0);
+    
+    a.insert(0, 0);
+    a.insert(1, 1);
+    a.insert(64, 64);
+    let stats = a.memory_stats();
+    assert_eq!(stats.levels, [
+        LevelMemoryStats{ nodes: 1, allocated_slots: 4, used_slots: 2, mask_bytes: node_mask_bytes, bytes: node_bytes(4, ptr_size) },
+        LevelMemoryStats{ nodes: 2, allocated_slots: 6, used_slots: 3, mask_bytes: 2 * node_mask_bytes, bytes: node_bytes(4, index_size) + node_bytes(2, index_size) },
+    ]);
+    assert_eq!(stats.nodes(), 3);
+    assert_eq!(stats.data_bytes, a.data.capacity() * size_of::<usize>());
+    assert_eq!(stats.keys_bytes, a.keys.capacity() * size_of::<usize>());
+    
+    // Removed node goes to pool.
+    a.remove(64);
+    let stats = a.memory_stats();
+    assert_eq!(stats.levels[1].nodes, 1);
+    assert_eq!(stats.free_bytes, node_bytes(2, index_size));
+    a.shrink_to_fit();
+    assert_eq!(a.memory_stats().free_bytes, 0);
+}
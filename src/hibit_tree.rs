@@ -1,12 +1,13 @@
 use std::borrow::Borrow;
 use std::marker::PhantomData;
-use std::ops::RangeTo;
-use crate::{multi_map_fold, BitBlock};
-use crate::const_utils::{ConstArray, ConstInteger};
+use std::ops::{RangeBounds, RangeTo};
+use crate::{data_block_index, multi_map_fold, BitBlock};
+use crate::bit_queue::BitQueue;
+use crate::const_utils::{const_loop, ConstArray, ConstArrayType, ConstInteger, ConstUsize};
 use crate::iter::Iter;
 use crate::level_indices;
-use crate::ops::{Map, MapFunction, MultiMapFold};
-use crate::utils::{BinaryFunction, Borrowable, NullaryFunction};
+use crate::ops::{CollectEach, ExtendOne, Filter, FilterMap, FilterMapFunction, Map, MapFunction, MultiMapFold};
+use crate::utils::{Array, BinaryFunction, Borrowable, DoubleEndedLendingIterator, LendingIterator, NullaryFunction, UnaryFunction};
 
 // Should be just <const WIDTH: usize, const DEPTH: usize>, but RUST not yet
 // support that for our case.
@@ -144,6 +145,9 @@ where
 {
     /// Marker that this tree as a bitmap hierarchy does not have
     /// false-positive bits in bitmasks.
+    /// 
+    /// Terminal level masks must be exact regardless - iteration 
+    /// relies on that.
     const EXACT_HIERARCHY: bool;
     
     /// Hierarchy levels count (without a data level).
@@ -184,6 +188,74 @@ where
     fn iter(&self) -> Iter<Self>{
         Iter::new(self)
     }
+    
+    /// Iterate indices within `range`, in ascending order.
+    /// 
+    /// Unlike filtering [iter()] output, skips everything outside of `range`
+    /// at hierarchy level. For lazy trees, this means that only nodes 
+    /// intersecting `range` are ever computed.
+    /// 
+    /// [iter()]: Self::iter
+    #[inline]
+    fn range(&self, range: impl RangeBounds<usize>) -> Iter<'_, Self>{
+        Iter::range(self, range)
+    }
+
+    /// Element with the smallest index.
+    #[inline]
+    fn first(&self) -> Option<(usize, <Self as HibitTreeTypes<'_>>::Data)> {
+        self.lower_bound(0)
+    }
+    
+    /// Element with the largest index.
+    #[inline]
+    fn last(&self) -> Option<(usize, <Self as HibitTreeTypes<'_>>::Data)> {
+        self.upper_bound(usize::MAX)
+    }
+    
+    /// First element with index >= `index`.
+    /// 
+    /// Descends the hierarchy, picking the nearest set bit at each level -
+    /// O(depth) for [EXACT_HIERARCHY], no scanning. Otherwise - same as
+    /// the first element of `range(index..)`.
+    /// 
+    /// [EXACT_HIERARCHY]: Self::EXACT_HIERARCHY
+    #[inline]
+    fn lower_bound(&self, index: usize) -> Option<(usize, <Self as HibitTreeTypes<'_>>::Data)> {
+        let index = if Self::EXACT_HIERARCHY {
+            nearest_index::<Self, false>(self, index)?
+        } else {
+            LendingIterator::next(&mut self.range(index..))?.0
+        };
+        Some((index, self.get(index)?))
+    }
+    
+    /// Last element with index <= `index`.
+    /// 
+    /// Mirror of [lower_bound].
+    /// 
+    /// [lower_bound]: Self::lower_bound
+    #[inline]
+    fn upper_bound(&self, index: usize) -> Option<(usize, <Self as HibitTreeTypes<'_>>::Data)> {
+        let index = if Self::EXACT_HIERARCHY {
+            nearest_index::<Self, true>(self, index)?
+        } else {
+            DoubleEndedLendingIterator::next_back(&mut self.range(..=index))?.0
+        };
+        Some((index, self.get(index)?))
+    }
+    
+    /// First element with index > `index`.
+    #[inline]
+    fn next_after(&self, index: usize) -> Option<(usize, <Self as HibitTreeTypes<'_>>::Data)> {
+        self.lower_bound(index.checked_add(1)?)
+    }
+    
+    /// Last element with index < `index`.
+    #[inline]
+    fn prev_before(&self, index: usize) -> Option<(usize, <Self as HibitTreeTypes<'_>>::Data)> {
+        self.upper_bound(index.checked_sub(1)?)
+    }
 
     /// You can use `usize` or [Index] for `index`.
     #[inline]
@@ -206,6 +278,51 @@ where
         self.data_unchecked(index, indices.as_ref())
     }
     
+    /// Does element at `index` exist?
+    /// 
+    /// For [EXACT_HIERARCHY] - checks bitmasks only, without touching data.
+    /// Otherwise - same as `get(index).is_some()`.
+    /// 
+    /// [EXACT_HIERARCHY]: Self::EXACT_HIERARCHY
+    #[inline]
+    fn contains(&self, index: impl Into<Index<<Self as HibitTree>::LevelMask, Self::LevelCount>>) -> bool {
+        let index: usize = index.into().into();
+        if !Self::EXACT_HIERARCHY {
+            return self.get(index).is_some();
+        }
+        
+        let indices = level_indices::<Self::LevelMask, Self::LevelCount>(index);
+        let mut cursor = <Self as HibitTreeTypes<'_>>::Cursor::new(self);
+        let mut node_index = 0;
+        const_loop!(N in 0..{<Self::LevelCount as ConstInteger>::VALUE} => {
+            let mask = unsafe{ cursor.select_level_node(self, ConstUsize::<N>, node_index) };
+            node_index = unsafe{ *indices.as_ref().get_unchecked(N) };
+            if !mask.get_bit(node_index) {
+                return false;
+            }
+        });
+        true
+    }
+    
+    /// Is there no elements in tree?
+    /// 
+    /// For [EXACT_HIERARCHY] - this is just a root bitmask check.
+    /// Otherwise - descends the tree until the first element found.
+    /// 
+    /// [EXACT_HIERARCHY]: Self::EXACT_HIERARCHY
+    #[inline]
+    fn is_empty(&self) -> bool {
+        if Self::EXACT_HIERARCHY {
+            let mut cursor = <Self as HibitTreeTypes<'_>>::Cursor::new(self);
+            let root_mask = unsafe{
+                cursor.select_level_node_unchecked(self, ConstUsize::<0>, 0)
+            };
+            root_mask.is_zero()
+        } else {
+            LendingIterator::next(&mut self.iter()).is_none()
+        }
+    }
+    
     /// Index range this SparseHierarchy can handle - `0..width^depth`.
     /// 
     /// Indices outside of this range considered to be invalid.
@@ -217,6 +334,86 @@ where
     }
 }
 
+/// Nearest existing index to `index` - at or after it, or at or before it for `REV`.
+/// 
+/// Descends `index` path as deep as it exists, trimming each level's mask to 
+/// the `index` side. Then picks the nearest remaining bit of the deepest 
+/// non-empty level, and descends its first (last for `REV`) bits.
+/// 
+/// `T` must be [EXACT_HIERARCHY] - each set bit must lead to data.
+/// 
+/// [EXACT_HIERARCHY]: HibitTree::EXACT_HIERARCHY
+#[inline]
+fn nearest_index<T: HibitTree, const REV: bool>(tree: &T, index: usize) -> Option<usize> {
+    debug_assert!(T::EXACT_HIERARCHY);
+    let index = if index >= T::index_range().end {
+        if !REV {
+            return None;
+        }
+        T::index_range().end - 1
+    } else {
+        index
+    };
+    
+    let mut path = level_indices::<T::LevelMask, T::LevelCount>(index);
+    let mut level_iters: ConstArrayType<<T::LevelMask as BitBlock>::BitsIter, T::LevelCount> = 
+        Array::from_fn(|_| BitQueue::empty());
+    let mut cursor = <T as HibitTreeTypes<'_>>::Cursor::new(tree);
+    
+    // Deepest level of `index` path, that exists.
+    let mut depth = 0;
+    const_loop!(N in 0..{<T::LevelCount as ConstInteger>::VALUE} => 'descend: {
+        let node_index = if N == 0 { 0 } else { unsafe{ *path.as_ref().get_unchecked(N - 1) } };
+        let inner_index = unsafe{ *path.as_ref().get_unchecked(N) };
+        let mask = unsafe{ cursor.select_level_node_unchecked(tree, ConstUsize::<N>, node_index) };
+        let level_iter = unsafe{ level_iters.as_mut().get_unchecked_mut(N) };
+        *level_iter = mask.into_bits_iter();
+        let current = if REV {
+            level_iter.trim_after(inner_index);
+            level_iter.current_back()
+        } else {
+            level_iter.trim_to(inner_index);
+            level_iter.current()
+        };
+        depth = N;
+        
+        if current != inner_index {
+            break 'descend;
+        }
+        if N == T::LevelCount::VALUE - 1 {
+            return Some(index);
+        }
+        // `index` path node itself is not in the nearest side.
+        if REV { level_iter.next_back(); } else { level_iter.next(); }
+    });
+    
+    // Nearest side node, on the deepest possible level.
+    let (level, inner_index) = (0..=depth).rev().find_map(|n| {
+        let level_iter = unsafe{ level_iters.as_mut().get_unchecked_mut(n) };
+        let inner_index = if REV { level_iter.next_back() } else { level_iter.next() }?;
+        Some((n, inner_index))
+    })?;
+    unsafe{ *path.as_mut().get_unchecked_mut(level) = inner_index; }
+    
+    // Its first (last for REV) element.
+    let mut descend = false;
+    const_loop!(N in 0..{<T::LevelCount as ConstInteger>::VALUE - 1} => {
+        descend |= N == level;
+        if descend {
+            unsafe{
+                let node_index = *path.as_ref().get_unchecked(N);
+                let level_iter = cursor.select_level_node_unchecked(tree, ConstUsize::<N>.inc(), node_index)
+                    .into_bits_iter();
+                *path.as_mut().get_unchecked_mut(N + 1) = 
+                    if REV { level_iter.current_back() } else { level_iter.current() };
+            }
+        }
+    });
+    
+    let data_index = *path.as_ref().last().unwrap();
+    Some(data_block_index::<T::LevelCount, T::LevelMask>(&path, data_index))
+}
+
 
 /// [HibitTree] that is not a concrete collection.
 /// 
@@ -358,6 +555,24 @@ where
     {
         crate::map(self, f)
     }
+
+    /// See [crate::filter]
+    #[inline]
+    fn filter<F>(self, f: F) -> Filter<Self, F>
+    where
+        F: for<'a> UnaryFunction<<Self as HibitTreeTypes<'a>>::Data, Output = bool>,
+    {
+        crate::filter(self, f)
+    }
+
+    /// See [crate::filter_map]
+    #[inline]
+    fn filter_map<F>(self, f: F) -> FilterMap<Self, F>
+    where
+        F: for<'a> FilterMapFunction<'a, <Self as HibitTreeTypes<'a>>::Data>
+    {
+        crate::filter_map(self, f)
+    }
 }
 
 impl<'this, T> RegularHibitTreeTypes<'this> for T
@@ -411,4 +626,37 @@ where
     {
         multi_map_fold(self, init, f)
     }
+    
+    /// Collect each element's items into `C`.
+    /// 
+    /// Result can be [materialize]d into container, like `DenseTree<Vec<_>, DEPTH>`.
+    /// Hierarchy is built from masks directly - same as with any other [LazyHibitTree].
+    /// 
+    /// [materialize]: LazyHibitTree::materialize
+    #[inline]
+    fn collect_each<C>(self) -> CollectEach<Self, C>
+    where
+        C: Default,
+        C: for<'a> Extend<<Self as MultiHibitTreeTypes<'a>>::IterItem>
+    {
+        multi_map_fold(self, C::default as fn() -> C, ExtendOne)
+    }
+    
+    /// [map_fold] each element and [materialize] result into `T`.
+    /// 
+    /// [map_fold]: Self::map_fold
+    /// [materialize]: LazyHibitTree::materialize
+    #[inline]
+    fn collect_into<T, I, F>(self, init: I, f: F) -> T
+    where 
+        I: NullaryFunction,
+        F: for<'a> BinaryFunction<
+            I::Output, 
+            <Self as MultiHibitTreeTypes<'a>>::IterItem,
+            Output = I::Output
+        >,
+        T: FromHibitTree<MultiMapFold<Self, I, F>>
+    {
+        multi_map_fold(self, init, f).materialize()
+    }
 }
\ No newline at end of file
@@ -1,10 +1,11 @@
-use std::ops::ControlFlow;
+use std::ops::{Bound, ControlFlow, RangeBounds};
 use crate::hibit_tree::{HibitTree, HibitTreeCursor};
-use crate::{BitBlock, data_block_index, RegularHibitTree, HibitTreeCursorTypes, HibitTreeTypes};
+use crate::{BitBlock, data_block_index, level_indices, RegularHibitTree, HibitTreeCursorTypes, HibitTreeTypes};
 use crate::bit_queue::BitQueue;
 use crate::const_utils::const_int::{const_for_rev, ConstInteger, ConstIntVisitor, ConstUsize};
+use crate::const_utils::const_loop;
 use crate::const_utils::const_array::ConstArrayType;
-use crate::utils::LendingIterator;
+use crate::utils::{DoubleEndedLendingIterator, LendingIterator};
 use crate::utils::Array;
 
 // TODO: could be u8's
@@ -27,6 +28,7 @@ type LevelIterators<T: HibitTree> =
 /// [HibitTree] iterator.
 ///  
 /// This is [LendingIterator], that also [Iterator] for [RegularHibitTree]. 
+/// Double-ended - [DoubleEndedLendingIterator] and [DoubleEndedIterator] respectively.
 pub struct Iter<'a, T>
 where
     T: HibitTree,
@@ -38,8 +40,17 @@ where
     
     /// [usize; T::LevelCount - 1]
     level_indices: LevelIndices<T>,
+    
+    /// Last index to iterate, inclusive.
+    end: usize,
 
     cursor: <T as HibitTreeTypes<'a>>::Cursor,
+    
+    /// First index to iterate.
+    start: usize,
+    
+    /// Constructed on first `next_back()`.
+    back: Option<Back<'a, T>>,
 }
 
 impl<'a, T> Iter<'a, T>
@@ -67,9 +78,269 @@ where
             // usize::MAX - is marker, that we're in "intial state".
             // Which means that only level0_iter initialized, and in original state.
             level_indices: Array::from_fn(|_| usize::MAX),
+            
+            end: usize::MAX,
 
             cursor,
+            
+            start: 0,
+            
+            back: None,
+        }
+    }
+    
+    /// Iterate only indices within `range`.
+    /// 
+    /// Starting position found by descending the `range` start path and trimming
+    /// each level's [BitQueue] - nodes before it are never touched.
+    /// Iteration stops at the first node past `range` end.
+    pub fn range(container: &'a T, range: impl RangeBounds<usize>) -> Self {
+        let mut this = Self::new(container);
+        
+        let start = match range.start_bound() {
+            Bound::Included(&start) => Some(start),
+            Bound::Excluded(&start) => start.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => Some(end),
+            Bound::Excluded(&end) => end.checked_sub(1),
+            Bound::Unbounded => Some(usize::MAX),
+        };
+        let (start, end) = match (start, end) {
+            (Some(start), Some(end)) 
+                if start <= end && start < T::index_range().end => (start, end),
+            _ => {
+                this.finish();
+                let mut back = Back::new(container, usize::MAX);
+                back.finish();
+                this.back = Some(back);
+                return this;
+            }
+        };
+        this.start = start;
+        this.end = end;
+        
+        let indices = level_indices::<T::LevelMask, T::LevelCount>(start);
+        const_loop!(N in 0..{<T::LevelCount as ConstInteger>::VALUE} => 'descend: {
+            let inner_index = unsafe{ *indices.as_ref().get_unchecked(N) };
+            let level_iter = unsafe{ this.level_iters.as_mut().get_unchecked_mut(N) };
+            level_iter.trim_to(inner_index);
+            
+            // If there is no node at `start` path - lower levels stay empty,
+            // and iteration will continue from the next node of this level.
+            if N == T::LevelCount::VALUE - 1 || level_iter.current() != inner_index {
+                break 'descend;
+            }
+            level_iter.next();
+            
+            unsafe{
+                *this.level_indices.as_mut().get_unchecked_mut(N) = inner_index;
+                let level_mask = this.cursor.select_level_node_unchecked(
+                    container, ConstUsize::<N>.inc(), inner_index
+                );
+                *this.level_iters.as_mut().get_unchecked_mut(N + 1) = level_mask.into_bits_iter();
+            }
+        });
+        
+        this
+    }
+    
+    /// Make all further `next()` calls return `None`.
+    #[inline]
+    fn finish(&mut self) {
+        for level_iter in self.level_iters.as_mut() {
+            *level_iter = BitQueue::empty();
+        }
+    }
+    
+    /// First index of the node at level `n`, with `level_indices` path.
+    #[inline]
+    fn node_start(&self, n: usize) -> usize {
+        let level_count = T::LevelCount::VALUE;
+        let mut acc = 0;
+        for i in 0..=n {
+            acc += self.level_indices.as_ref()[i] << (T::LevelMask::SIZE.ilog2() as usize * (level_count - i - 1));
         }
+        acc
+    }
+    
+    /// Forward iteration will not return indices before this.
+    /// 
+    /// Derived from the forward traverse state, so forward iteration 
+    /// does not need to track it. `None` - if it will return nothing.
+    #[inline]
+    fn front_limit(&self) -> Option<usize> {
+        // Not descended yet - nothing returned yet.
+        if self.level_indices.as_ref().contains(&usize::MAX) {
+            return Some(self.start);
+        }
+        let last_level_iter = self.level_iters.as_ref().last().unwrap();
+        data_block_index::<T::LevelCount, T::LevelMask>(&self.level_indices, 0)
+            .checked_add(last_level_iter.current())
+    }
+}
+
+/// [Iter] reverse traverse state.
+/// 
+/// Same as [Iter] forward state, but [BitQueue]s are consumed from the back.
+struct Back<'a, T>
+where
+    T: HibitTree,
+{
+    /// [T::LevelMaskType::BitsIter; T::LevelCount]
+    level_iters: LevelIterators<T>,
+    
+    /// [usize; T::LevelCount - 1]
+    level_indices: LevelIndices<T>,
+
+    cursor: <T as HibitTreeTypes<'a>>::Cursor,
+}
+
+impl<'a, T> Back<'a, T>
+where
+    T: HibitTree,
+{
+    /// Positioned at `end`, inclusive.
+    #[inline]
+    fn new(container: &'a T, end: usize) -> Self {
+        let mut level_iters: LevelIterators<T> = Array::from_fn(|_| BitQueue::empty());
+        
+        let mut cursor = T::Cursor::new(container);
+        
+        let root_mask = unsafe{
+            cursor.select_level_node_unchecked(container, ConstUsize::<0>, 0)
+        };
+        level_iters.as_mut()[0] = root_mask.into_bits_iter();
+        
+        let mut this = Self{
+            level_iters,
+            level_indices: Array::from_fn(|_| usize::MAX),
+            cursor,
+        };
+        if end >= T::index_range().end {
+            return this;
+        }
+        
+        let indices = level_indices::<T::LevelMask, T::LevelCount>(end);
+        const_loop!(N in 0..{<T::LevelCount as ConstInteger>::VALUE} => 'descend: {
+            let inner_index = unsafe{ *indices.as_ref().get_unchecked(N) };
+            let level_iter = unsafe{ this.level_iters.as_mut().get_unchecked_mut(N) };
+            level_iter.trim_after(inner_index);
+            
+            // If there is no node at `end` path - lower levels stay empty,
+            // and iteration will continue from the previous node of this level.
+            if N == T::LevelCount::VALUE - 1 || level_iter.current_back() != inner_index {
+                break 'descend;
+            }
+            level_iter.next_back();
+            
+            unsafe{
+                *this.level_indices.as_mut().get_unchecked_mut(N) = inner_index;
+                let level_mask = this.cursor.select_level_node_unchecked(
+                    container, ConstUsize::<N>.inc(), inner_index
+                );
+                *this.level_iters.as_mut().get_unchecked_mut(N + 1) = level_mask.into_bits_iter();
+            }
+        });
+        
+        this
+    }
+    
+    /// Make all further `next()` calls return `None`.
+    #[inline]
+    fn finish(&mut self) {
+        for level_iter in self.level_iters.as_mut() {
+            *level_iter = BitQueue::empty();
+        }
+    }
+    
+    /// Last index of the node at level `n`, with `level_indices` path.
+    #[inline]
+    fn node_last(&self, n: usize) -> usize {
+        let level_count = T::LevelCount::VALUE;
+        let level_exp = |i: usize| T::LevelMask::SIZE.ilog2() as usize * (level_count - i - 1);
+        let mut acc = 0;
+        for i in 0..=n {
+            acc += self.level_indices.as_ref()[i] << level_exp(i);
+        }
+        acc + ((1 << level_exp(n)) - 1)
+    }
+    
+    /// Previous data not before `limit`.
+    /// 
+    /// Returns data level index and index.
+    #[inline]
+    fn next(&mut self, container: &'a T, limit: usize) -> Option<(usize, usize)> {
+        let level_index = loop {
+            let last_level_iter = self.level_iters.as_mut().last_mut().unwrap();
+            if let Some(index) = last_level_iter.next_back() {
+                break index;
+            } else {
+                let ctrl = const_for_rev(
+                    ConstUsize::<0>, T::LevelCount::DEFAULT.dec(), 
+                    V{back: self, container, limit}
+                );
+                struct V<'b,'a,T: HibitTree>{
+                    back: &'b mut Back<'a, T>,
+                    container: &'a T,
+                    limit: usize,
+                }
+                impl<'b,'a,T: HibitTree> ConstIntVisitor for V<'b,'a,T> {
+                    type Out = ();
+                    #[inline(always)]
+                    fn visit<I: ConstInteger>(&mut self, i: I) -> ControlFlow<()> {
+                        let level_iter = unsafe{
+                            self.back
+                            .level_iters.as_mut()
+                            .get_unchecked_mut(i.value())
+                        };
+                        if let Some(index) = level_iter.next_back(){
+                            unsafe{
+                                *self.back
+                                    .level_indices.as_mut()
+                                    .get_unchecked_mut(i.value()) 
+                                    = index; 
+                            }
+                            
+                            // Whole node is before the limit.
+                            if self.back.node_last(i.value()) < self.limit {
+                                self.back.finish();
+                                return ControlFlow::Break(());
+                            }
+                            
+                            let level_depth = i.inc();
+                            let level_mask = unsafe{
+                                self.back.cursor.select_level_node_unchecked(
+                                    self.container,
+                                    level_depth,
+                                    index
+                                )
+                            };
+                            *unsafe{
+                                self.back
+                                .level_iters.as_mut()
+                                .get_unchecked_mut(level_depth.value())
+                            } = level_mask.into_bits_iter(); 
+                            
+                            ControlFlow::Break(())
+                        } else {
+                            ControlFlow::Continue(())
+                        }
+                    }
+                }
+                if ctrl.is_continue(){
+                    return None;
+                }
+            }
+        };
+        
+        let block_index = data_block_index::<T::LevelCount, T::LevelMask>(&self.level_indices, level_index);
+        if block_index < limit {
+            self.finish();
+            return None;
+        }
+        Some((level_index, block_index))
     }
 }
 
@@ -110,6 +381,12 @@ where
                                     = index; 
                             }
                             
+                            // Whole node is past the range end.
+                            if self.0.node_start(i.value()) > self.0.end {
+                                self.0.finish();
+                                return ControlFlow::Break(());
+                            }
+                            
                             // 2. update level_iter from mask
                             let level_depth = i.inc();                            
                             let level_mask = unsafe{
@@ -139,14 +416,45 @@ where
             }
         };
 
+        let block_index = data_block_index::<T::LevelCount, T::LevelMask>(&self.level_indices, level_index);
+        if block_index > self.end {
+            self.finish();
+            return None;
+        }
         let data_block = unsafe {
             self.cursor.data_unchecked(&self.container, level_index)
         };
-        let block_index = data_block_index::<T::LevelCount, T::LevelMask>(&self.level_indices, level_index);
         Some((block_index, data_block))
     }    
 }
 
+impl<'a, T> DoubleEndedLendingIterator for Iter<'a, T>
+where
+    T: HibitTree,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item<'_>> {
+        let limit = self.front_limit()?;
+        let container = self.container;
+        let end = self.end;
+        let back = self.back.get_or_insert_with(|| Back::new(container, end));
+        let (level_index, block_index) = back.next(container, limit)?;
+        
+        // Forward iteration stops before what we returned.
+        if let Some(end) = block_index.checked_sub(1) {
+            self.end = end;
+        } else {
+            self.finish();
+        }
+        
+        let back = self.back.as_mut().unwrap();
+        let data_block = unsafe {
+            back.cursor.data_unchecked(self.container, level_index)
+        };
+        Some((block_index, data_block))
+    }
+}
+
 
 impl<'a, T> Iterator for Iter<'a, T>
 where
@@ -158,4 +466,14 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         LendingIterator::next(self)
     }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T>
+where
+    T: RegularHibitTree,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        DoubleEndedLendingIterator::next_back(self)
+    }
 }
\ No newline at end of file
@@ -1,10 +1,13 @@
 use crate::level::ILevel;
-use crate::MaybeEmptyIntrusive;
+use crate::alloc_vec::AllocVec;
+use crate::{Global, MaybeEmptyIntrusive, NodeAllocator};
 
 /// Level that uses intrusive list for an empty blocks list.
+/// 
+/// Blocks memory comes from `A`.
 #[derive(Clone)]
-pub struct IntrusiveListLevel<Block: MaybeEmptyIntrusive>{
-    blocks: Vec<Block>,
+pub struct IntrusiveListLevel<Block: MaybeEmptyIntrusive, A: NodeAllocator = Global>{
+    blocks: AllocVec<Block, A>,
     
     /// Single linked list of empty level_block indices.
     /// Mask of empty level_block used as a "next free level_block".
@@ -12,18 +15,7 @@ pub struct IntrusiveListLevel<Block: MaybeEmptyIntrusive>{
     root_empty_block: u64,
 }
 
-impl<Block: MaybeEmptyIntrusive> Default for IntrusiveListLevel<Block> {
-    #[inline]
-    fn default() -> Self {
-        Self{
-            //Always have empty level_block at index 0.
-            blocks:vec![Block::empty()],
-            root_empty_block: u64::MAX,
-        }
-    }
-}
-
-impl<Block: MaybeEmptyIntrusive> IntrusiveListLevel<Block> {
+impl<Block: MaybeEmptyIntrusive, A: NodeAllocator> IntrusiveListLevel<Block, A> {
     /// Next empty level_block link
     /// 
     /// Block's mask used as index to next empty level_block
@@ -40,7 +32,7 @@ impl<Block: MaybeEmptyIntrusive> IntrusiveListLevel<Block> {
             
         let index = self.root_empty_block as usize;
         unsafe{
-            let empty_block = self.blocks.get_unchecked_mut(index);
+            let empty_block = self.blocks.as_mut_slice().get_unchecked_mut(index);
             let next_empty_block_index = Self::next_empty_block_index(empty_block); 
             
             // update list root 
@@ -57,7 +49,7 @@ impl<Block: MaybeEmptyIntrusive> IntrusiveListLevel<Block> {
     /// level_block must be empty and not in use!
     #[inline]
     unsafe fn push_empty_block(&mut self, block_index: usize){
-        let empty_block = self.blocks.get_unchecked_mut(block_index);
+        let empty_block = self.blocks.as_mut_slice().get_unchecked_mut(block_index);
         let next_empty_block_index = Self::next_empty_block_index(empty_block);
         *next_empty_block_index = self.root_empty_block;
         
@@ -65,8 +57,20 @@ impl<Block: MaybeEmptyIntrusive> IntrusiveListLevel<Block> {
     }
 }
 
-impl<Block: MaybeEmptyIntrusive> ILevel for IntrusiveListLevel<Block> {
+impl<Block: MaybeEmptyIntrusive, A: NodeAllocator> ILevel for IntrusiveListLevel<Block, A> {
     type Block = Block;
+    type Allocator = A;
+
+    #[inline]
+    fn new_in(allocator: A) -> Self {
+        let mut blocks = AllocVec::new_in(allocator);
+        //Always have empty level_block at index 0.
+        blocks.push(Block::empty());
+        Self{
+            blocks,
+            root_empty_block: u64::MAX,
+        }
+    }
 
     #[inline]
     fn blocks(&self) -> &[Self::Block] {
@@ -78,6 +82,11 @@ impl<Block: MaybeEmptyIntrusive> ILevel for IntrusiveListLevel<Block> {
         self.blocks.as_mut_slice()
     }
 
+    #[inline]
+    fn blocks_capacity(&self) -> usize {
+        self.blocks.capacity()
+    }
+
     #[inline]
     fn insert_empty_block(&mut self) -> usize {
         if let Some(index) = self.pop_empty_block(){
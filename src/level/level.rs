@@ -1,27 +1,28 @@
 use crate::level::ILevel;
-use crate::Empty;
+use crate::{Empty, Global};
 
 /// Simple level implementation. Works with all `Block`s.
 ///
 /// Prefer using [IntrusiveListLevel] whenever possible.
+/// Blocks memory always comes from [Global].
+#[derive(Clone)]
 pub struct Level<Block: Empty>{
     blocks: Vec<Block>,
     empty_block_indices: Vec<usize>
 }
 
-impl<Block: Empty> Default for Level<Block> {
+impl<Block: Empty> ILevel for Level<Block> {
+    type Block = Block;
+    type Allocator = Global;
+
     #[inline]
-    fn default() -> Self {
+    fn new_in(_: Global) -> Self {
         Self{
             //Always have empty level_block at index 0.
             blocks:vec![Block::empty()],
             empty_block_indices: Vec::new()
         }
     }
-}
-
-impl<Block: Empty> ILevel for Level<Block> {
-    type Block = Block;
 
     #[inline]
     fn blocks(&self) -> &[Self::Block] {
@@ -33,6 +34,11 @@ impl<Block: Empty> ILevel for Level<Block> {
         self.blocks.as_mut_slice()
     }
 
+    #[inline]
+    fn blocks_capacity(&self) -> usize {
+        self.blocks.capacity()
+    }
+
     #[inline]
     fn insert_empty_block(&mut self) -> usize {
         if let Some(index) = self.empty_block_indices.pop(){
@@ -6,15 +6,23 @@ pub use single_block_level::*;
 pub use intrusive_list_level::*;
 pub use level::*;
 
-use crate::Empty;
+use crate::{Empty, NodeAllocator};
 
-pub trait ILevel: Default {
+pub trait ILevel {
     // TODO: Now it is always "HiBlock"
     type Block: Empty;
     
+    /// Blocks memory source.
+    type Allocator: NodeAllocator;
+    
+    fn new_in(allocator: Self::Allocator) -> Self;
+    
     fn blocks(&self) -> &[Self::Block];
     fn blocks_mut(&mut self) -> &mut [Self::Block];
     
+    /// Number of blocks level can hold without reallocation.
+    fn blocks_capacity(&self) -> usize;
+    
     fn insert_empty_block(&mut self) -> usize;
     
     /// # Safety
@@ -1,14 +1,25 @@
+use std::marker::PhantomData;
 use std::slice;
 use crate::level::ILevel;
-use crate::Empty;
+use crate::{Empty, Global, NodeAllocator};
 
+/// Level with one inline block.
+///
+/// `A` - tree allocator. Not used by level itself.
 #[derive(Clone)]
-pub struct SingleBlockLevel<Block: Empty>{
-    block: Block
+pub struct SingleBlockLevel<Block: Empty, A: NodeAllocator = Global>{
+    block: Block,
+    phantom: PhantomData<A>
 }
 
-impl<Block: Empty> ILevel for SingleBlockLevel<Block>{
+impl<Block: Empty, A: NodeAllocator> ILevel for SingleBlockLevel<Block, A>{
     type Block = Block;
+    type Allocator = A;
+
+    #[inline]
+    fn new_in(_: A) -> Self {
+        Self{ block: Block::empty(), phantom: PhantomData }
+    }
 
     #[inline]
     fn blocks(&self) -> &[Self::Block] {
@@ -20,6 +31,11 @@ impl<Block: Empty> ILevel for SingleBlockLevel<Block>{
         unsafe{ slice::from_raw_parts_mut(&mut self.block, 1) }
     }
 
+    #[inline]
+    fn blocks_capacity(&self) -> usize {
+        1
+    }
+
     fn insert_empty_block(&mut self) -> usize {
         unreachable!()
     }
@@ -29,9 +45,3 @@ impl<Block: Empty> ILevel for SingleBlockLevel<Block>{
     }
 }
 
-impl<Block: Empty> Default for SingleBlockLevel<Block> {
-    #[inline]
-    fn default() -> Self {
-        Self{ block: Block::empty() }
-    }
-}
@@ -114,11 +114,13 @@
 //! 
 //! Speeds up following operations:
 //! - [FromHibitTree]
-//! - TODO `Eq`
-//! - TODO `is_empty()`
-//! - TODO `contains()`
+//! - [hibit_eq] / [hibit_cmp]
+//! - [is_empty()]
+//! - [contains()]
 //! 
 //! [EXACT_HIERARCHY]: HibitTree::EXACT_HIERARCHY
+//! [is_empty()]: HibitTree::is_empty
+//! [contains()]: HibitTree::contains
 //! 
 //! ## Flags
 //! 
@@ -138,9 +140,14 @@ mod bit_utils;
 mod bit_block;
 mod hibit_tree;
 mod iter;
+mod cmp;
 mod level;
 mod level_block;
 mod req_default;
+mod prune;
+mod node_allocator;
+mod alloc_vec;
+mod memory_stats;
 
 pub mod ops;
 pub mod bit_queue;
@@ -154,12 +161,31 @@ pub use bit_block::BitBlock;
 pub use req_default::ReqDefault;
 pub use sparse_tree::SparseTree;
 pub use dense_tree::DenseTree;
+pub use node_allocator::{NodeAllocator, Global};
+pub use memory_stats::{MemoryStats, LevelMemoryStats};
+
+/// [DenseTree] related types.
+pub mod dense {
+    pub use crate::dense_tree::{DataIndex, Entry, OccupiedEntry, VacantEntry};
+}
+
+/// [SparseTree] related types.
+pub mod sparse {
+    pub use crate::sparse_tree::{Entry, OccupiedEntry, VacantEntry};
+}
 pub use hibit_tree::*;
 pub use iter::*;
+pub use cmp::{hibit_eq, hibit_cmp, DataPartialEq, DataPartialOrd};
 pub use ops::map::map;
+pub use ops::filter::filter;
+pub use ops::filter_map::filter_map;
 pub use ops::multi_map_fold::multi_map_fold;
 pub use ops::intersection::intersection;
 pub use ops::union::union;
+pub use ops::union_with::union_with;
+pub use ops::difference::difference;
+pub use ops::symmetric_difference::symmetric_difference;
+pub use ops::shift::shift;
 pub use ops::_multi_intersection::multi_intersection;
 pub use ops::_multi_union::multi_union;
 
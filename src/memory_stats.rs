@@ -0,0 +1,91 @@
+/// Tree memory usage.
+///
+/// Returned by [DenseTree::memory_stats] and [SparseTree::memory_stats].
+/// All sizes are in bytes, and count allocated capacity, not just used part.
+///
+/// ```
+/// # use hibit_tree::{config, DenseTree, SparseTree};
+/// let dense: DenseTree<usize, 4> = (0..1000).map(|i| (i * 100, i)).collect();
+/// let sparse: SparseTree<config::width_64::depth_4, usize> = (0..1000).map(|i| (i * 100, i)).collect();
+///
+/// let dense_stats = dense.memory_stats();
+/// let sparse_stats = sparse.memory_stats();
+/// assert_eq!(dense_stats.levels[3].used_slots, 1000);
+/// assert_eq!(sparse_stats.levels[3].used_slots, 1000);
+/// assert!(dense_stats.node_bytes() < sparse_stats.node_bytes());
+/// ```
+///
+/// [DenseTree::memory_stats]: crate::DenseTree::memory_stats
+/// [SparseTree::memory_stats]: crate::SparseTree::memory_stats
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Per level stats, starting from root.
+    pub levels: Vec<LevelMemoryStats>,
+
+    /// Allocated, but not used by tree nodes.
+    ///
+    /// For [DenseTree] - pooled nodes. For [SparseTree] - empty blocks
+    /// and unused level storage capacity.
+    ///
+    /// [DenseTree]: crate::DenseTree
+    /// [SparseTree]: crate::SparseTree
+    pub free_bytes: usize,
+
+    /// Empty node/block sentinels, that empty children point to.
+    ///
+    /// [DenseTree] sentinels are static, and shared between all trees.
+    ///
+    /// [DenseTree]: crate::DenseTree
+    pub sentinel_bytes: usize,
+
+    /// Elements storage.
+    pub data_bytes: usize,
+
+    /// Keys storage, with other per-element bookkeeping.
+    pub keys_bytes: usize,
+}
+
+impl MemoryStats {
+    /// Nodes in use, across all levels.
+    #[inline]
+    pub fn nodes(&self) -> usize {
+        self.levels.iter().map(|level| level.nodes).sum()
+    }
+
+    /// Bytes of nodes in use, across all levels.
+    #[inline]
+    pub fn node_bytes(&self) -> usize {
+        self.levels.iter().map(|level| level.bytes).sum()
+    }
+
+    /// All memory, accounted by stats.
+    #[inline]
+    pub fn total_bytes(&self) -> usize {
+        self.node_bytes()
+        + self.free_bytes
+        + self.sentinel_bytes
+        + self.data_bytes
+        + self.keys_bytes
+    }
+}
+
+/// Single tree level memory usage. Part of [MemoryStats].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LevelMemoryStats {
+    /// Nodes in use.
+    pub nodes: usize,
+
+    /// Child slots, allocated in nodes.
+    pub allocated_slots: usize,
+
+    /// Child slots, occupied by existing children.
+    pub used_slots: usize,
+
+    /// Node masks. Part of [bytes].
+    ///
+    /// [bytes]: Self::bytes
+    pub mask_bytes: usize,
+
+    /// Nodes in use.
+    pub bytes: usize,
+}
@@ -0,0 +1,97 @@
+use std::alloc::{alloc, dealloc, handle_alloc_error, realloc, Layout};
+use std::ptr::{self, NonNull};
+
+/// Memory source for trees.
+///
+/// Used by [DenseTree] for nodes, and by [SparseTree] for level blocks.
+/// Elements and keys storage use it as well.
+///
+/// Stable counterpart of unstable [std::alloc::Allocator]. Trees never call
+/// [deallocate()] for memory that is still in use, so arena allocators can
+/// implement it as no-op, and free everything at once.
+///
+/// Trees keep a clone of the allocator for each of their storages - so
+/// they require allocator to be a cheap [Clone] handle, like `&Arena`.
+///
+/// # Safety
+///
+/// Memory returned by [allocate()]/[reallocate()] must be valid for the requested
+/// layout, and stay valid until passed to [deallocate()]/[reallocate()].
+///
+/// [DenseTree]: crate::DenseTree
+/// [SparseTree]: crate::SparseTree
+/// [allocate()]: Self::allocate
+/// [reallocate()]: Self::reallocate
+/// [deallocate()]: Self::deallocate
+pub unsafe trait NodeAllocator {
+    /// Allocate memory block for `layout`.
+    ///
+    /// Must not return on failure - use [handle_alloc_error] or panic.
+    fn allocate(&self, layout: Layout) -> NonNull<u8>;
+
+    /// # Safety
+    ///
+    /// `ptr` must be allocated by this allocator with `layout`.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+    /// Change memory block size, preserving content.
+    ///
+    /// Default implementation allocates a new block, copies and deallocates
+    /// the old one.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be allocated by this allocator with `layout`.
+    /// `new_size` must be non-zero, and fit `isize` when rounded up to
+    /// `layout.align()`.
+    #[inline]
+    unsafe fn reallocate(&self, ptr: NonNull<u8>, layout: Layout, new_size: usize) -> NonNull<u8> {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new_ptr = self.allocate(new_layout);
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), layout.size().min(new_size));
+        self.deallocate(ptr, layout);
+        new_ptr
+    }
+}
+
+/// Global allocator. Default [NodeAllocator].
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Global;
+
+unsafe impl NodeAllocator for Global {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> NonNull<u8> {
+        let ptr = unsafe{ alloc(layout) };
+        NonNull::new(ptr).unwrap_or_else(|| handle_alloc_error(layout))
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        dealloc(ptr.as_ptr(), layout)
+    }
+
+    #[inline]
+    unsafe fn reallocate(&self, ptr: NonNull<u8>, layout: Layout, new_size: usize) -> NonNull<u8> {
+        let new_ptr = realloc(ptr.as_ptr(), layout, new_size);
+        NonNull::new(new_ptr).unwrap_or_else(|| {
+            handle_alloc_error(Layout::from_size_align_unchecked(new_size, layout.align()))
+        })
+    }
+}
+
+unsafe impl<A: NodeAllocator + ?Sized> NodeAllocator for &A {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> NonNull<u8> {
+        (**self).allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        (**self).deallocate(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn reallocate(&self, ptr: NonNull<u8>, layout: Layout, new_size: usize) -> NonNull<u8> {
+        (**self).reallocate(ptr, layout, new_size)
+    }
+}
@@ -126,12 +126,12 @@ where
 {
     cursors: ArrayVec<CursorsItem<'item, Iter>, N>,
     
-    /// [ArrayVec<usize, N>; Array::LevelCount - 1]
+    /// [ArrayVec<usize, N>; Array::LevelCount]
     /// 
-    /// Root level skipped.
+    /// Cursors with non-empty node at each level.
     lvls_non_empty_states: ConstArrayType<
         ArrayVec<CursorIndex, N>,
-        <<IterItem<Iter> as HibitTree>::LevelCount as ConstInteger>::Dec,
+        <IterItem<Iter> as HibitTree>::LevelCount,
     >,
     
     phantom_data: PhantomData<&'src MultiUnion<Iter>>
@@ -182,19 +182,11 @@ where
     {
         let mut acc_mask = BitBlock::zero();
         
-        if N::VALUE == 0 {
-            for (array, array_cursor) in self.cursors.iter_mut() {
-                let mask = array_cursor.select_level_node(array, level_n, level_index);
-                acc_mask |= mask;
-            }            
-            return acc_mask;
-        }
-        
         // drop lifetime checks for `get_many`-like access. 
         let mut lvls_non_empty_states = NonNull::from(self.lvls_non_empty_states.as_mut());
         
         let lvl_non_empty_states = 
-            lvls_non_empty_states.as_mut().get_unchecked_mut(level_n.value()-1);
+            lvls_non_empty_states.as_mut().get_unchecked_mut(level_n.value());
         lvl_non_empty_states.clear();
         
         let len = self.cursors.len() as u8;
@@ -208,13 +200,12 @@ where
             acc_mask |= mask;            
         };
         
-        if N::VALUE == 1 {
-            // Prev level is root. Since we don't store root - 
-            // just iterate all states.
+        if N::VALUE == 0 {
+            // Root - iterate all states.
             for i in 0..len { foreach(i) }    
         } else {
             let prev_lvl_non_empty_states =
-                lvls_non_empty_states.as_ref().get_unchecked(level_n.value()-2);
+                lvls_non_empty_states.as_ref().get_unchecked(level_n.value()-1);
             for i in prev_lvl_non_empty_states { foreach(*i) }
         }
         
@@ -225,10 +216,6 @@ where
     unsafe fn data<'a>(&'a self, src: &'src Self::Src, level_index: usize) 
         -> Option<<Self as HibitTreeCursorTypes<'a>>::Data> 
     {
-        if <Self::Src as HibitTree>::LevelCount::VALUE == 1 {
-            todo!("TODO: compile-time special case for 1-level SparseHierarchy");
-        }
-        
         let lvl_non_empty_states = self.lvls_non_empty_states.as_ref()
                                    .last().unwrap_unchecked();
         if lvl_non_empty_states.is_empty(){
@@ -342,7 +329,7 @@ mod tests{
     use itertools::assert_equal;
     use crate::dense_tree::DenseTree;
     use crate::hibit_tree::HibitTree;
-    use crate::utils::LendingIterator;
+    use crate::utils::{DoubleEndedLendingIterator, LendingIterator};
 
     #[test]
     fn smoke_test(){
@@ -374,6 +361,13 @@ mod tests{
             v.push(values);
         }
         
+        let mut rev_indices = Vec::new();
+        let mut iter = union.iter().rev();
+        while let Some((index, _)) = iter.next(){
+            rev_indices.push(index);
+        }
+        assert_equal(rev_indices, [300, 200, 100, 15, 10]);
+        
         assert_equal(v, vec![
             vec![arrays[0].get(10).unwrap()],
             vec![
@@ -0,0 +1,243 @@
+use std::marker::PhantomData;
+use crate::const_utils::ConstInteger;
+use crate::{LazyHibitTree, HibitTreeCursorTypes, HibitTreeTypes};
+use crate::hibit_tree::{HibitTree, HibitTreeCursor};
+use crate::utils::Borrowable;
+use crate::cmp::impl_hibit_tree_cmp;
+
+/// Elements of `S0`, that are not in `S1`.
+///
+/// Constructed by [difference()].
+pub struct Difference<S0, S1>{
+    s0: S0,
+    s1: S1
+}
+
+impl<'this, S0, S1> HibitTreeTypes<'this> for Difference<S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree<
+        LevelCount = <S0::Borrowed as HibitTree>::LevelCount,
+        LevelMask  = <S0::Borrowed as HibitTree>::LevelMask,
+    >>,
+{
+    type Data = <S0::Borrowed as HibitTreeTypes<'this>>::Data;
+    type DataUnchecked = <S0::Borrowed as HibitTreeTypes<'this>>::DataUnchecked;
+    type Cursor = Cursor<'this, S0, S1>;
+}
+
+impl<S0, S1> HibitTree for Difference<S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree<
+        LevelCount = <S0::Borrowed as HibitTree>::LevelCount,
+        LevelMask  = <S0::Borrowed as HibitTree>::LevelMask,
+    >>,
+{
+    /// Intermediate node of `S0` may contain only elements of `S1`.
+    const EXACT_HIERARCHY: bool = false;
+
+    type LevelCount = <S0::Borrowed as HibitTree>::LevelCount;
+    type LevelMask  = <S0::Borrowed as HibitTree>::LevelMask;
+
+    #[inline]
+    unsafe fn data(&self, index: usize, level_indices: &[usize])
+        -> Option<<Self as HibitTreeTypes<'_>>::Data>
+    {
+        let d0 = self.s0.borrow().data(index, level_indices)?;
+        if self.s1.borrow().data(index, level_indices).is_some() {
+            return None;
+        }
+        Some(d0)
+    }
+
+    #[inline]
+    unsafe fn data_unchecked(&self, index: usize, level_indices: &[usize])
+        -> <Self as HibitTreeTypes<'_>>::DataUnchecked
+    {
+        self.s0.borrow().data_unchecked(index, level_indices)
+    }
+}
+
+pub struct Cursor<'src, S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree>,
+{
+    s0: <S0::Borrowed as HibitTreeTypes<'src>>::Cursor,
+    s1: <S1::Borrowed as HibitTreeTypes<'src>>::Cursor,
+    phantom: PhantomData<&'src Difference<S0, S1>>
+}
+
+impl<'this, 'src, S0, S1> HibitTreeCursorTypes<'this> for Cursor<'src, S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree>,
+{
+    type Data = <<S0::Borrowed as HibitTreeTypes<'src>>::Cursor as HibitTreeCursorTypes<'this>>::Data;
+}
+
+impl<'src, S0, S1> Cursor<'src, S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree<
+        LevelCount = <S0::Borrowed as HibitTree>::LevelCount,
+        LevelMask  = <S0::Borrowed as HibitTree>::LevelMask,
+    >>,
+{
+    /// `mask0` - `S0` node mask at `level_n`.
+    #[inline]
+    unsafe fn difference_mask<N: ConstInteger>(
+        &mut self, this: &'src Difference<S0, S1>, level_n: N, level_index: usize,
+        mask0: <S0::Borrowed as HibitTree>::LevelMask
+    ) -> <S0::Borrowed as HibitTree>::LevelMask {
+        // S1 node may not exist. Selected at every level anyway - S1 cursor
+        // must follow the path down to the terminal level.
+        let mask1 = self.s1.select_level_node(
+            this.s1.borrow(), level_n, level_index
+        );
+
+        // Only terminal level bits can be subtracted. Intermediate S1 bit
+        // means that S1 has *some* element in that subtree - not that all
+        // S0 elements of that subtree are in S1. Clearing it would lose S0
+        // elements, that S1 does not have. So intermediate masks are S0's,
+        // and resulting node may end up empty - hence no EXACT_HIERARCHY.
+        // Terminal level masks are exact - subtraction there is precise.
+        if N::VALUE == <S0::Borrowed as HibitTree>::LevelCount::VALUE - 1 {
+            mask0 & !mask1
+        } else {
+            mask0
+        }
+    }
+}
+
+impl<'src, S0, S1> HibitTreeCursor<'src> for Cursor<'src, S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree<
+        LevelCount = <S0::Borrowed as HibitTree>::LevelCount,
+        LevelMask  = <S0::Borrowed as HibitTree>::LevelMask,
+    >>,
+{
+    type Src = Difference<S0, S1>;
+
+    #[inline]
+    fn new(this: &'src Self::Src) -> Self {
+        Self{
+            s0: HibitTreeCursor::new(this.s0.borrow()),
+            s1: HibitTreeCursor::new(this.s1.borrow()),
+            phantom: PhantomData
+        }
+    }
+
+    #[inline]
+    unsafe fn select_level_node<N: ConstInteger>(
+        &mut self, this: &'src Self::Src, level_n: N, level_index: usize
+    ) -> <Self::Src as HibitTree>::LevelMask {
+        let mask0 = self.s0.select_level_node(
+            this.s0.borrow(), level_n, level_index
+        );
+        self.difference_mask(this, level_n, level_index, mask0)
+    }
+
+    #[inline]
+    unsafe fn select_level_node_unchecked<N: ConstInteger> (
+        &mut self, this: &'src Self::Src, level_n: N, level_index: usize
+    ) -> <Self::Src as HibitTree>::LevelMask {
+        let mask0 = self.s0.select_level_node_unchecked(
+            this.s0.borrow(), level_n, level_index
+        );
+        self.difference_mask(this, level_n, level_index, mask0)
+    }
+
+    #[inline]
+    unsafe fn data<'a>(&'a self, this: &'src Self::Src, level_index: usize)
+        -> Option<<Self as HibitTreeCursorTypes<'a>>::Data>
+    {
+        let d0 = self.s0.data(this.s0.borrow(), level_index)?;
+        if self.s1.data(this.s1.borrow(), level_index).is_some() {
+            return None;
+        }
+        Some(d0)
+    }
+
+    #[inline]
+    unsafe fn data_unchecked<'a>(&'a self, this: &'src Self::Src, level_index: usize)
+        -> <Self as HibitTreeCursorTypes<'a>>::Data
+    {
+        // Terminal mask already excludes S1 elements.
+        self.s0.data_unchecked(this.s0.borrow(), level_index)
+    }
+}
+
+impl<S0, S1> LazyHibitTree for Difference<S0, S1>
+where
+    Difference<S0, S1>: HibitTree
+{}
+
+impl<S0, S1> Borrowable for Difference<S0, S1>{ type Borrowed = Self; }
+
+impl_hibit_tree_cmp!([S0, S1] Difference<S0, S1>);
+
+/// Elements of `s0`, whose indices are not in `s1`.
+///
+/// Only `s0` data is returned, `s1` data is never touched during iteration.
+#[inline]
+pub fn difference<S0, S1>(s0: S0, s1: S1) -> Difference<S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree<
+        LevelCount = <S0::Borrowed as HibitTree>::LevelCount,
+        LevelMask  = <S0::Borrowed as HibitTree>::LevelMask,
+    >>,
+{
+    Difference { s0, s1 }
+}
+
+#[cfg(test)]
+mod tests{
+    use itertools::assert_equal;
+    use crate::dense_tree::DenseTree;
+    use crate::ops::difference::difference;
+    use crate::ops::intersection::intersection;
+    use crate::hibit_tree::HibitTree;
+    use crate::utils::LendingIterator;
+
+    #[test]
+    fn smoke_test(){
+        type Array = DenseTree<usize, 3>;
+        let a: Array = [(10, 10), (15, 15), (200, 200), (70_000, 70_000)].into_iter().collect();
+        let b: Array = [(15, 15), (100, 100), (70_000, 70_000)].into_iter().collect();
+
+        let diff = difference(&a, &b);
+        assert_equal(diff.iter(), [(10, &10), (200, &200)]);
+        assert_eq!(diff.get(10), Some(&10));
+        assert_eq!(diff.get(15), None);
+        assert_eq!(diff.get(100), None);
+        assert!(!diff.contains(70_000));
+
+        // Whole terminal node of `a` is in `b`.
+        let diff = difference(&b, &a);
+        assert_equal(diff.iter(), [(100, &100)]);
+        assert!(difference(&a, &a).is_empty());
+        assert_eq!(LendingIterator::next(&mut difference(&a, &a).iter()), None);
+    }
+
+    #[test]
+    fn non_exact_test(){
+        type Array = DenseTree<usize, 3>;
+        let a: Array = (0..5000).map(|k| (k, k)).collect();
+        let b1: Array = (0..5000).step_by(2).map(|k| (k, k)).collect();
+        let b2: Array = (1..5000).step_by(2).map(|k| (k, k)).chain([(4, 4)]).collect();
+
+        // b1 & b2 = {4}, with non-exact hierarchy all over the tree.
+        let b = intersection(&b1, &b2);
+        let diff = difference(&a, &b);
+        assert_equal(diff.iter().map(|(k, _)| k), (0..5000).filter(|&k| k != 4));
+        assert_equal(diff.range(3..6).map(|(k, _)| k), [3, 5]);
+
+        // Non-exact minuend.
+        let diff = difference(intersection(&a, &b1), &b2);
+        assert_equal(diff.iter().map(|(k, _)| k), (0..5000).step_by(2).filter(|&k| k != 4));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{filter_map, HibitTreeTypes, RegularHibitTree};
+use crate::ops::FilterMap;
+use crate::ops::filter_map::private::FilterMapResult;
+use crate::utils::{Borrowable, UnaryFunction};
+
+/// `bool` predicate, as [filter_map] function.
+///
+/// Returns the element itself, if accepted by predicate.
+#[derive(Clone, Copy)]
+pub struct FilterFn<F>(F);
+
+impl<F, I> UnaryFunction<I> for FilterFn<F>
+where
+    F: UnaryFunction<I, Output = bool>
+{
+    type Output = Accepted;
+
+    #[inline]
+    fn exec(&self, arg: I) -> Accepted {
+        Accepted(self.0.exec(arg))
+    }
+}
+
+/// [FilterFn] result.
+///
+/// Element itself is fetched from source once more on access,
+/// instead of being cloned for predicate.
+#[doc(hidden)]
+pub struct Accepted(bool);
+
+impl<I> FilterMapResult<I> for Accepted {
+    type Accepted = ();
+    type Output = I;
+
+    #[inline]
+    fn accepted(self) -> Option<()> {
+        self.0.then_some(())
+    }
+
+    #[inline]
+    fn output(_: (), arg: impl FnOnce() -> I) -> I {
+        arg()
+    }
+}
+
+/// See [filter].
+pub type Filter<S, F> = FilterMap<S, FilterFn<F>>;
+
+/// [RegularHibitTree] elements, for which `f: Fn(Item) -> bool` returns `true`.
+///
+/// [FilterMap] with `f` wrapped into [FilterFn]. Rejected elements are dropped
+/// from terminal node masks during traversal, so `f` is evaluated once for each
+/// element of every visited terminal node. Accepted elements are fetched
+/// from `s` on access.
+///
+/// Same as with [map], you'll have to specify lambda argument type:
+///
+/// ```
+/// # use hibit_tree::{filter, intersection, DenseTree, HibitTree};
+/// # use itertools::assert_equal;
+/// let scores: DenseTree<usize, 4> = [(1, 5), (2, 20), (3, 30)].into_iter().collect();
+/// let names : DenseTree<&str, 4>  = [(2, "two"), (3, "three")].into_iter().collect();
+///
+/// let i = intersection(filter(&scores, |s: &usize| *s > 25), &names);
+/// assert_equal(i.iter(), [(3, (&30, &"three"))]);
+/// ```
+///
+/// [map]: crate::map
+#[inline]
+pub fn filter<S, F>(s: S, f: F) -> Filter<S, F>
+where
+    S: Borrowable<Borrowed: RegularHibitTree>,
+    F: for<'a> UnaryFunction<<S::Borrowed as HibitTreeTypes<'a>>::Data, Output = bool>,
+{
+    filter_map(s, FilterFn(f))
+}
+
+#[cfg(test)]
+mod tests{
+    use itertools::assert_equal;
+    use crate::dense_tree::DenseTree;
+    use crate::hibit_tree::{HibitTree, LazyHibitTree, RegularHibitTree};
+    use crate::ops::filter::filter;
+    use crate::ops::union::union;
+
+    #[test]
+    fn smoke_test(){
+        type Array = DenseTree<usize, 3>;
+        let a: Array = (0..5000).map(|k| (k, k)).collect();
+
+        let even = filter(&a, |v: &usize| v % 2 == 0);
+        assert_equal(even.iter().map(|(k, _)| k), (0..5000).step_by(2));
+        assert_equal(even.range(11..15).map(|(k, _)| k), [12, 14]);
+        assert_eq!(even.get(10), Some(&10));
+        assert_eq!(even.get(11), None);
+        assert_eq!(even.last(), Some((4998, &4998)));
+
+        // Whole terminal nodes are rejected.
+        let none = filter(&a, |v: &usize| *v == usize::MAX);
+        assert!(none.is_empty());
+        assert_eq!(none.iter().next(), None);
+
+        let u = union(filter(&a, |v: &usize| *v < 3), filter(&a, |v: &usize| *v > 4996));
+        assert_equal(u.iter().map(|(k, _)| k), [0, 1, 2, 4997, 4998, 4999]);
+
+        let m: Array = filter(&a, |v: &usize| v % 1000 == 0).map(|v: &usize| *v).materialize();
+        assert_equal(m.iter(), [(0, &0), (1000, &1000), (2000, &2000), (3000, &3000), (4000, &4000)]);
+    }
+}
@@ -0,0 +1,324 @@
+use std::cell::Cell;
+use std::marker::PhantomData;
+use crate::{BitBlock, LazyHibitTree, RegularHibitTree, HibitTree, HibitTreeCursor, HibitTreeCursorTypes, HibitTreeTypes};
+use crate::const_utils::ConstInteger;
+use crate::utils::{Borrowable, UnaryFunction};
+use crate::cmp::impl_hibit_tree_cmp;
+use private::FilterMapResult;
+
+pub(super) mod private {
+    pub trait Sealed<I> {} // Users in other crates cannot name this trait.
+
+    impl<F, I, R> Sealed<I> for F
+    where
+        F: super::UnaryFunction<I, Output = R>,
+        R: FilterMapResult<I>
+    {}
+
+    /// [FilterMapFunction] result.
+    ///
+    /// [FilterMapFunction]: super::FilterMapFunction
+    pub trait FilterMapResult<I> {
+        type Accepted;
+        type Output;
+        fn accepted(self) -> Option<Self::Accepted>;
+        fn output(accepted: Self::Accepted, arg: impl FnOnce() -> I) -> Self::Output;
+    }
+
+    impl<I, O> FilterMapResult<I> for Option<O> {
+        type Accepted = O;
+        type Output = O;
+
+        #[inline]
+        fn accepted(self) -> Option<O> {
+            self
+        }
+
+        #[inline]
+        fn output(accepted: O, _: impl FnOnce() -> I) -> O {
+            accepted
+        }
+    }
+}
+
+/// `Fn(Item) -> Option<Out>`.
+///
+/// Evaluated in two steps: [accept] on traversal, [output] on element access.
+/// So [filter] predicate does not need to produce element itself.
+///
+/// [accept]: Self::accept
+/// [output]: Self::output
+/// [filter]: crate::filter
+pub trait FilterMapFunction<'a, I>: private::Sealed<I> {
+    type Output;
+
+    /// Accepted element result, kept until access.
+    #[doc(hidden)]
+    type Accepted;
+
+    #[doc(hidden)]
+    fn accept(&self, arg: I) -> Option<Self::Accepted>;
+
+    /// `arg` - fetches the same element once more.
+    #[doc(hidden)]
+    fn output(accepted: Self::Accepted, arg: impl FnOnce() -> I) -> Self::Output;
+}
+
+impl<'a, I, F, R> FilterMapFunction<'a, I> for F
+where
+    F: UnaryFunction<I, Output = R>,
+    R: FilterMapResult<I>
+{
+    type Output = R::Output;
+    type Accepted = R::Accepted;
+
+    #[inline]
+    fn accept(&self, arg: I) -> Option<R::Accepted> {
+        self.exec(arg).accepted()
+    }
+
+    #[inline]
+    fn output(accepted: R::Accepted, arg: impl FnOnce() -> I) -> R::Output {
+        R::output(accepted, arg)
+    }
+}
+
+const IMPURE_F: &str = "filter_map function rejected previously accepted element";
+
+pub struct FilterMap<S, F>{
+    s: S,
+    f: F,
+}
+
+impl<'this, S, F> HibitTreeTypes<'this> for FilterMap<S, F>
+where
+    S: Borrowable<Borrowed: RegularHibitTree>,
+    F: for<'a> FilterMapFunction<'a, <S::Borrowed as HibitTreeTypes<'a>>::Data>
+{
+    type Data = <F as FilterMapFunction<'this, <S::Borrowed as HibitTreeTypes<'this>>::Data>>::Output;
+    type DataUnchecked = Self::Data;
+    type Cursor = Cursor<'this, S, F>;
+}
+
+impl<S, F> HibitTree for FilterMap<S, F>
+where
+    S: Borrowable<Borrowed: RegularHibitTree>,
+    F: for<'a> FilterMapFunction<'a, <S::Borrowed as HibitTreeTypes<'a>>::Data>
+{
+    /// Intermediate nodes may contain only rejected elements.
+    const EXACT_HIERARCHY: bool = false;
+
+    type LevelCount = <S::Borrowed as HibitTree>::LevelCount;
+    type LevelMask  = <S::Borrowed as HibitTree>::LevelMask;
+
+    #[inline]
+    unsafe fn data(&self, index: usize, level_indices: &[usize])
+        -> Option<<Self as HibitTreeTypes<'_>>::Data>
+    {
+        let s = self.s.borrow();
+        let accepted = self.f.accept(s.data(index, level_indices)?)?;
+        Some(F::output(accepted, || s.data_unchecked(index, level_indices)))
+    }
+
+    #[inline]
+    unsafe fn data_unchecked(&self, index: usize, level_indices: &[usize])
+        -> <Self as HibitTreeTypes<'_>>::DataUnchecked
+    {
+        let s = self.s.borrow();
+        // Not unchecked - impure `f` may reject element, that was
+        // accepted by some other traversal.
+        let accepted = self.f.accept(s.data_unchecked(index, level_indices)).expect(IMPURE_F);
+        F::output(accepted, || s.data_unchecked(index, level_indices))
+    }
+}
+
+impl<'this, 'src, S, F> HibitTreeCursorTypes<'this> for Cursor<'src, S, F>
+where
+    S: Borrowable<Borrowed: RegularHibitTree>,
+    F: for<'a> FilterMapFunction<'a, <S::Borrowed as HibitTreeTypes<'a>>::Data>
+{
+    type Data = <FilterMap<S, F> as HibitTreeTypes<'src>>::Data;
+}
+
+type Accepted<'src, S, F> = <F as FilterMapFunction<'src, <<S as Borrowable>::Borrowed as HibitTreeTypes<'src>>::Data>>::Accepted;
+
+pub struct Cursor<'src, S, F>
+where
+    S: Borrowable<Borrowed: RegularHibitTree>,
+    F: for<'a> FilterMapFunction<'a, <S::Borrowed as HibitTreeTypes<'a>>::Data>
+{
+    cursor: <S::Borrowed as HibitTreeTypes<'src>>::Cursor,
+
+    /// Current terminal node elements, accepted by `f`.
+    /// Zero, if cursor is not at terminal level.
+    accepted_mask: <S::Borrowed as HibitTree>::LevelMask,
+
+    /// `f` results for `accepted_mask` elements, by in-node index.
+    /// Taken on access, so `f` is evaluated once per element.
+    accepted: Vec<Cell<Option<Accepted<'src, S, F>>>>,
+
+    phantom: PhantomData<&'src FilterMap<S, F>>
+}
+
+impl<'src, S, F> Cursor<'src, S, F>
+where
+    S: Borrowable<Borrowed: RegularHibitTree>,
+    F: for<'a> FilterMapFunction<'a, <S::Borrowed as HibitTreeTypes<'a>>::Data>
+{
+    /// Drop terminal node `mask` elements, for which `f` returns `None`.
+    /// Results of accepted ones are kept for data access.
+    #[inline]
+    unsafe fn filter_mask<N: ConstInteger>(
+        &mut self, src: &'src FilterMap<S, F>, mask: <S::Borrowed as HibitTree>::LevelMask
+    ) -> <S::Borrowed as HibitTree>::LevelMask {
+        if N::VALUE != <S::Borrowed as HibitTree>::LevelCount::VALUE - 1 {
+            self.accepted_mask = BitBlock::zero();
+            return mask;
+        }
+        if self.accepted.is_empty() {
+            self.accepted.resize_with(<S::Borrowed as HibitTree>::LevelMask::SIZE, Default::default);
+        }
+        let mut filtered = mask.clone();
+        for index in mask.into_bits_iter() {
+            let data = self.cursor.data_unchecked(src.s.borrow(), index);
+            let accepted = src.f.accept(data);
+            if accepted.is_none() {
+                filtered.set_bit::<false>(index);
+            }
+            self.accepted.get_unchecked(index).set(accepted);
+        }
+        self.accepted_mask = filtered.clone();
+        filtered
+    }
+}
+
+impl<'src, S, F> HibitTreeCursor<'src> for Cursor<'src, S, F>
+where
+    S: Borrowable<Borrowed: RegularHibitTree>,
+    F: for<'a> FilterMapFunction<'a, <S::Borrowed as HibitTreeTypes<'a>>::Data>
+{
+    type Src = FilterMap<S, F>;
+
+    #[inline]
+    fn new(this: &'src Self::Src) -> Self {
+        Self{
+            cursor: HibitTreeCursor::new(this.s.borrow()),
+            accepted_mask: BitBlock::zero(),
+            accepted: Vec::new(),
+            phantom: PhantomData
+        }
+    }
+
+    #[inline]
+    unsafe fn select_level_node<N: ConstInteger>(
+        &mut self, src: &'src Self::Src, level_n: N, level_index: usize
+    ) -> <Self::Src as HibitTree>::LevelMask {
+        let mask = self.cursor.select_level_node(src.s.borrow(), level_n, level_index);
+        self.filter_mask::<N>(src, mask)
+    }
+
+    #[inline]
+    unsafe fn select_level_node_unchecked<N: ConstInteger>(
+        &mut self, src: &'src Self::Src, level_n: N, level_index: usize
+    ) -> <Self::Src as HibitTree>::LevelMask {
+        let mask = self.cursor.select_level_node_unchecked(src.s.borrow(), level_n, level_index);
+        self.filter_mask::<N>(src, mask)
+    }
+
+    #[inline]
+    unsafe fn data<'a>(&'a self, this: &'src Self::Src, level_index: usize)
+        -> Option<<Self as HibitTreeCursorTypes<'a>>::Data>
+    {
+        if !self.accepted_mask.get_bit(level_index) {
+            return None;
+        }
+        Some(self.data_unchecked(this, level_index))
+    }
+
+    #[inline]
+    unsafe fn data_unchecked<'a>(&'a self, this: &'src Self::Src, level_index: usize)
+        -> <Self as HibitTreeCursorTypes<'a>>::Data
+    {
+        let s = this.s.borrow();
+        let accepted = match self.accepted.get(level_index).and_then(Cell::take) {
+            Some(accepted) => accepted,
+            // Accessed more than once.
+            None => this.f.accept(self.cursor.data_unchecked(s, level_index)).expect(IMPURE_F),
+        };
+        F::output(accepted, || self.cursor.data_unchecked(s, level_index))
+    }
+}
+
+impl<S, F> LazyHibitTree for FilterMap<S, F>
+where
+    FilterMap<S, F>: RegularHibitTree
+{}
+
+impl<S, F> Borrowable for FilterMap<S, F> { type Borrowed = Self; }
+
+impl_hibit_tree_cmp!([S, F] FilterMap<S, F>);
+
+/// Maps each [RegularHibitTree] element with `f: Fn(Item) -> Option<Out>`,
+/// dropping elements for which `f` returns `None`.
+///
+/// `f` is evaluated for each element of every visited terminal node during
+/// traversal. Results of accepted elements are kept by cursor till access,
+/// so iteration evaluates `f` once per element. Random access ([get])
+/// evaluates `f` on each call.
+///
+/// [get]: HibitTree::get
+///
+/// Same as with [map], you'll have to specify lambda argument type.
+///
+/// [map]: crate::map
+#[inline]
+pub fn filter_map<S, F>(s: S, f: F) -> FilterMap<S, F>
+where
+    S: Borrowable<Borrowed: RegularHibitTree>,
+    F: for<'a> FilterMapFunction<'a, <S::Borrowed as HibitTreeTypes<'a>>::Data>
+{
+    FilterMap{ s, f }
+}
+
+#[cfg(test)]
+mod tests{
+    use itertools::assert_equal;
+    use crate::dense_tree::DenseTree;
+    use crate::hibit_tree::{HibitTree, LazyHibitTree};
+    use crate::ops::filter_map::filter_map;
+    use crate::ops::intersection::intersection;
+
+    #[test]
+    fn smoke_test(){
+        type Array = DenseTree<usize, 3>;
+        let a: Array = (0..5000).map(|k| (k, k)).collect();
+
+        let halves = filter_map(&a, |v: &usize| (v % 2 == 0).then_some(v / 2));
+        assert_equal(halves.iter().take(3), [(0, 0), (2, 1), (4, 2)]);
+        assert_equal(halves.iter().rev().take(2), [(4998, 2499), (4996, 2498)]);
+        assert_eq!(halves.get(100), Some(50));
+        assert_eq!(halves.get(101), None);
+        assert!(filter_map(&a, |_: &usize| None::<usize>).is_empty());
+
+        let b: Array = (0..5000).step_by(3).map(|k| (k, k)).collect();
+        let i = intersection(filter_map(&a, |v: &usize| (v % 2 == 0).then_some(*v)), &b);
+        assert_equal(i.iter().map(|(k, _)| k), (0..5000).step_by(6));
+
+        let m: Array = filter_map(&a, |v: &usize| (v % 1000 == 0).then_some(v + 1)).materialize();
+        assert_equal(m.iter(), [(0, &1), (1000, &1001), (2000, &2001), (3000, &3001), (4000, &4001)]);
+    }
+
+    #[test]
+    fn single_call_test(){
+        use std::cell::Cell;
+        let a: DenseTree<usize, 3> = (0..1000).map(|k| (k, k)).collect();
+        let calls = Cell::new(0);
+        // Impure - accepts only first 10 calls.
+        let f = filter_map(&a, |v: &usize| {
+            calls.set(calls.get() + 1);
+            (calls.get() <= 10).then_some(*v)
+        });
+        assert_equal(f.iter(), (0..10).map(|k| (k, k)));
+        assert_eq!(calls.get(), 1000);
+    }
+}
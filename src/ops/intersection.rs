@@ -5,6 +5,7 @@ use crate::const_utils::{ConstArray, ConstInteger};
 use crate::{LazyHibitTree, RegularHibitTree, HibitTreeCursorTypes, HibitTreeTypes};
 use crate::hibit_tree::{HibitTree, HibitTreeCursor};
 use crate::utils::{Borrowable};
+use crate::cmp::impl_hibit_tree_cmp;
 
 pub struct Intersection<S0, S1>{
     s0: S0,
@@ -179,6 +180,8 @@ where
 
 impl<S0, S1> Borrowable for Intersection<S0, S1>{ type Borrowed = Self; }
 
+impl_hibit_tree_cmp!([S0, S1] Intersection<S0, S1>);
+
 #[inline]
 pub fn intersection<S0, S1>(s0: S0, s1: S1) -> Intersection<S0, S1>
 where
@@ -233,4 +236,80 @@ mod tests{
         
         assert_equal(i.iter(), [(15,30), (200, 400)]);*/
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn is_empty_contains_test(){
+        type Array = DenseTree<usize, 3>;
+        let mut a1= Array::default();
+        let mut a2= Array::default();
+        
+        // Same terminal node, no common keys - non-empty hierarchy,
+        // but no data.
+        a1.insert(10, 10);
+        a2.insert(11, 11);
+        
+        let intersect = intersection(&a1, &a2);
+        assert!(intersect.is_empty());
+        assert!(!intersect.contains(10));
+        assert!(!intersect.contains(11));
+        
+        a1.insert(11, 11);
+        let intersect = intersection(&a1, &a2);
+        assert!(!intersect.is_empty());
+        assert!(intersect.contains(11));
+        assert!(!intersect.contains(10));
+    }
+    
+    #[test]
+    fn range_test(){
+        type Array = DenseTree<usize, 3>;
+        let a1: Array = (0..5000).map(|k| (k, k)).collect();
+        let a2: Array = (0..5000).step_by(3).map(|k| (k, k)).collect();
+        
+        let intersect = intersection(&a1, &a2);
+        assert_equal(intersect.range(1000..2000).map(|(k,_)| k), (1002..2000).step_by(3));
+        assert_equal(intersect.range(1001..=1005).map(|(k,_)| k), [1002, 1005]);
+        assert_equal(intersect.range(4990..).map(|(k,_)| k), [4992, 4995, 4998]);
+        assert_equal(intersect.range(..7).map(|(k,_)| k), [0, 3, 6]);
+        assert_eq!(intersect.range(1003..1005).count(), 0);
+        assert_eq!(intersect.range(10..10).count(), 0);
+        assert_eq!(intersect.range(5000..).count(), 0);
+        assert_eq!(intersect.range(Array::index_range().end..).count(), 0);
+        assert_eq!(intersect.range(..).count(), intersect.iter().count());
+    }
+    
+    #[test]
+    fn bounds_test(){
+        type Array = DenseTree<usize, 3>;
+        let a1: Array = [(1, 1), (100, 100), (5000, 5000), (70_000, 70_000)].into_iter().collect();
+        let a2: Array = [(2, 2), (100, 100), (5001, 5001), (70_000, 70_000)].into_iter().collect();
+        
+        // Non-exact hierarchy: nodes around 1,2 and 5000,5001 have no common data.
+        let intersect = intersection(&a1, &a2);
+        let key = |v: Option<(usize, _)>| v.map(|(k, _)| k);
+        assert_eq!(key(intersect.first()), Some(100));
+        assert_eq!(key(intersect.last()), Some(70_000));
+        assert_eq!(key(intersect.lower_bound(100)), Some(100));
+        assert_eq!(key(intersect.lower_bound(101)), Some(70_000));
+        assert_eq!(key(intersect.lower_bound(70_001)), None);
+        assert_eq!(key(intersect.upper_bound(69_999)), Some(100));
+        assert_eq!(key(intersect.upper_bound(99)), None);
+        assert_eq!(key(intersect.next_after(100)), Some(70_000));
+        assert_eq!(key(intersect.prev_before(70_000)), Some(100));
+        assert_eq!(key(intersect.prev_before(0)), None);
+        assert_eq!(intersect.lower_bound(0), Some((100, (&100, &100))));
+        
+        assert_eq!(a1.first(), Some((1, &1)));
+        assert_eq!(a1.last(), Some((70_000, &70_000)));
+        assert_eq!(a1.upper_bound(4999), Some((100, &100)));
+        assert_eq!(a1.next_after(usize::MAX), None);
+        assert_eq!(Array::default().first(), None);
+        
+        // Lazy data computed once.
+        let calls = std::cell::Cell::new(0);
+        let m = crate::map(&a1, |v: &usize| { calls.set(calls.get() + 1); *v });
+        assert_eq!(m.lower_bound(2), Some((100, 100)));
+        assert_eq!(m.upper_bound(4999), Some((100, 100)));
+        assert_eq!(calls.get(), 2);
+    }
+}
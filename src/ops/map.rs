@@ -3,6 +3,7 @@ use std::marker::PhantomData;
 use crate::{LazyHibitTree, RegularHibitTree, HibitTree, HibitTreeCursor, HibitTreeCursorTypes, HibitTreeTypes};
 use crate::const_utils::ConstInteger;
 use crate::utils::{Borrowable, UnaryFunction};
+use crate::cmp::impl_hibit_tree_cmp;
 
 mod private {
     pub trait Sealed<I> {} // Users in other crates cannot name this trait.
@@ -147,6 +148,8 @@ where
 
 impl<S, F> Borrowable for Map<S, F> { type Borrowed = Self; }
 
+impl_hibit_tree_cmp!([S, F] Map<S, F>);
+
 /// Maps each [RegularHibitTree] element with `f: Fn(Item) -> Out`.
 /// 
 /// # Note
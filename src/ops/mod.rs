@@ -2,8 +2,16 @@ pub(crate) mod map;
 pub use map::{Map, MapFunction};
 
 
+pub(crate) mod filter;
+pub use filter::{Filter, FilterFn};
+
+
+pub(crate) mod filter_map;
+pub use filter_map::{FilterMap, FilterMapFunction};
+
+
 pub(crate) mod multi_map_fold;
-pub use multi_map_fold::MultiMapFold;
+pub use multi_map_fold::{MultiMapFold, CollectEach, ExtendOne};
 
 
 pub(crate) mod intersection;
@@ -14,6 +22,22 @@ pub(crate) mod union;
 pub use union::Union;
 
 
+pub(crate) mod union_with;
+pub use union_with::{UnionWith, UnionResolvers};
+
+
+pub(crate) mod difference;
+pub use difference::Difference;
+
+
+pub(crate) mod symmetric_difference;
+pub use symmetric_difference::SymmetricDifference;
+
+
+pub(crate) mod shift;
+pub use shift::Shift;
+
+
 pub(crate) mod _multi_intersection;
 pub mod multi_intersection {
     pub use super::_multi_intersection::{
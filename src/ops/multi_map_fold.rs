@@ -2,6 +2,7 @@ use std::marker::PhantomData;
 use crate::{LazyHibitTree, MultiHibitTree, MultiHibitTreeTypes, HibitTree, HibitTreeCursor, HibitTreeCursorTypes, HibitTreeTypes, RegularHibitTree};
 use crate::const_utils::ConstInteger;
 use crate::utils::{BinaryFunction, Borrowable, NullaryFunction, UnaryFunction};
+use crate::cmp::impl_hibit_tree_cmp;
 
 pub struct MultiMapFold<S, I, F>{
     s: S,
@@ -136,6 +137,8 @@ where
 
 impl<S, I, F> Borrowable for MultiMapFold<S, I, F>{ type Borrowed = Self; }
 
+impl_hibit_tree_cmp!([S, I, F] MultiMapFold<S, I, F>);
+
 impl<S, I, F> LazyHibitTree for MultiMapFold<S, I, F>
 where
     MultiMapFold<S, I, F>: HibitTree,
@@ -165,6 +168,30 @@ where
     MultiMapFold {s, init, f}   
 }
 
+/// [BinaryFunction] that extends collection with one item.
+/// 
+/// Used by [CollectEach].
+#[derive(Clone, Copy, Default)]
+pub struct ExtendOne;
+
+impl<C, T> BinaryFunction<C, T> for ExtendOne
+where
+    C: Extend<T>
+{
+    type Output = C;
+
+    #[inline]
+    fn exec(&self, mut c: C, item: T) -> C {
+        c.extend(std::iter::once(item));
+        c
+    }
+}
+
+/// [MultiHibitTree] with each element collected into `C`.
+/// 
+/// Constructed by [MultiHibitTree::collect_each].
+pub type CollectEach<S, C> = MultiMapFold<S, fn() -> C, ExtendOne>;
+
 #[cfg(test)]
 mod tests{
     use itertools::assert_equal;
@@ -197,4 +224,33 @@ mod tests{
         assert_eq!(unsafe{ intersect.get_unchecked(15) }, 45);
         assert_equal(intersect.iter(), [(15, 45)]);
     }
+    
+    #[test]
+    fn collect_test() {
+        use arrayvec::ArrayVec;
+        use crate::{multi_union, LazyHibitTree, MultiHibitTree};
+        
+        type Array = DenseTree<usize, 3>; 
+        let a1: Array = [(10, 10), (15, 15), (70_000, 70_000)].into_iter().collect();
+        let a2: Array = [(15, 16), (20, 20)].into_iter().collect();
+        let a3: Array = [(15, 17), (70_000, 70_001)].into_iter().collect();
+        let arrays = [a1, a2, a3];
+        
+        let vecs: DenseTree<Vec<&usize>, 3> = multi_union(arrays.iter()).collect_each().materialize();
+        assert_equal(vecs.iter(), [
+            (10, &vec![&10]), 
+            (15, &vec![&15, &16, &17]), 
+            (20, &vec![&20]), 
+            (70_000, &vec![&70_000, &70_001])
+        ]);
+        
+        // Non-exact hierarchy.
+        let vecs: DenseTree<ArrayVec<usize, 3>, 3> = 
+            multi_intersection(arrays.iter()).map_fold(ArrayVec::new, |mut v: ArrayVec<usize, 3>, i: &usize| { v.push(*i); v })
+            .materialize();
+        assert_equal(vecs.iter().map(|(k, v)| (k, v.as_slice())), [(15, [15, 16, 17].as_slice())]);
+        
+        let sums: DenseTree<usize, 3> = multi_union(arrays.iter()).collect_into(|| 0, |a, v: &usize| a + v);
+        assert_equal(sums.iter(), [(10, &10), (15, &48), (20, &20), (70_000, &140_001)]);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,390 @@
+use std::marker::PhantomData;
+use std::mem;
+use crate::const_utils::{const_loop, ConstInteger, ConstUsize};
+use crate::{level_indices, BitBlock, LazyHibitTree, HibitTreeCursorTypes, HibitTreeTypes};
+use crate::hibit_tree::{HibitTree, HibitTreeCursor};
+use crate::utils::Borrowable;
+use crate::cmp::impl_hibit_tree_cmp;
+
+type Mask<S> = <<S as Borrowable>::Borrowed as HibitTree>::LevelMask;
+type LevelCount<S> = <<S as Borrowable>::Borrowed as HibitTree>::LevelCount;
+
+/// Elements of `S`, with indices moved by `offset`.
+///
+/// Constructed by [shift()].
+pub struct Shift<S>{
+    s: S,
+    offset: usize,
+}
+
+impl<S> Shift<S>
+where
+    S: Borrowable<Borrowed: HibitTree>,
+{
+    /// log2 of `level` node span, in indices.
+    #[inline]
+    fn span_exp(level: usize) -> u32 {
+        Mask::<S>::SIZE.ilog2() * (LevelCount::<S>::VALUE - level) as u32
+    }
+
+    /// `offset` in `level` node spans: (quotient, remainder).
+    #[inline]
+    fn split_offset(&self, level: usize) -> (usize, usize) {
+        let exp = Self::span_exp(level);
+        if exp >= usize::BITS {
+            (0, self.offset)
+        } else {
+            (self.offset >> exp, self.offset & ((1 << exp) - 1))
+        }
+    }
+}
+
+impl<'this, S> HibitTreeTypes<'this> for Shift<S>
+where
+    S: Borrowable<Borrowed: HibitTree>,
+{
+    type Data = <S::Borrowed as HibitTreeTypes<'this>>::Data;
+    type DataUnchecked = <S::Borrowed as HibitTreeTypes<'this>>::DataUnchecked;
+    type Cursor = Cursor<'this, S>;
+}
+
+impl<S> HibitTree for Shift<S>
+where
+    S: Borrowable<Borrowed: HibitTree>,
+{
+    /// Unaligned node is assembled from two source nodes, and its children
+    /// may cover only empty parts of them.
+    const EXACT_HIERARCHY: bool = false;
+
+    type LevelCount = LevelCount<S>;
+    type LevelMask  = Mask<S>;
+
+    #[inline]
+    unsafe fn data(&self, index: usize, _: &[usize])
+        -> Option<<Self as HibitTreeTypes<'_>>::Data>
+    {
+        let index = index.checked_sub(self.offset)?;
+        let indices = level_indices::<Self::LevelMask, Self::LevelCount>(index);
+        self.s.borrow().data(index, indices.as_ref())
+    }
+
+    #[inline]
+    unsafe fn data_unchecked(&self, index: usize, _: &[usize])
+        -> <Self as HibitTreeTypes<'_>>::DataUnchecked
+    {
+        let index = index - self.offset;
+        let indices = level_indices::<Self::LevelMask, Self::LevelCount>(index);
+        self.s.borrow().data_unchecked(index, indices.as_ref())
+    }
+}
+
+/// Node position in the tree.
+#[derive(Clone, Copy)]
+struct NodePath{
+    level: usize,
+    /// Node index among all `level` nodes.
+    node: usize,
+}
+
+impl NodePath{
+    /// `node` ancestor at `level`.
+    #[inline]
+    fn ancestor<Mask: BitBlock>(&self, level: usize) -> usize {
+        self.node >> (Mask::SIZE.ilog2() as usize * (self.level - level))
+    }
+}
+
+/// Source cursor, that remembers its selected path.
+struct SourceCursor<'src, S>
+where
+    S: Borrowable<Borrowed: HibitTree>,
+{
+    cursor: <S::Borrowed as HibitTreeTypes<'src>>::Cursor,
+    path: Option<NodePath>,
+}
+
+impl<'src, S> SourceCursor<'src, S>
+where
+    S: Borrowable<Borrowed: HibitTree>,
+{
+    #[inline]
+    fn is_at(&self, level: usize, node: usize) -> bool {
+        matches!(self.path, Some(path) if path.level == level && path.node == node)
+    }
+
+    /// Is `level` `node` on the selected path?
+    #[inline]
+    fn is_within(&self, level: usize, node: usize) -> bool {
+        matches!(self.path, Some(path) if path.level >= level && path.ancestor::<Mask<S>>(level) == node)
+    }
+
+    /// Select source `node` at `level_n`.
+    ///
+    /// Only levels below the common ancestor with the currently selected
+    /// path are re-selected. For consecutive nodes of the same parent, this
+    /// is just one source node selection.
+    #[inline]
+    unsafe fn select<N: ConstInteger>(&mut self, src: &'src S::Borrowed, _: N, node: usize) -> Mask<S> {
+        let path = NodePath{ level: N::VALUE, node };
+        let from = match self.path {
+            Some(prev) if N::VALUE != 0 => {
+                // Root is always common.
+                let mut common = prev.level.min(N::VALUE - 1);
+                while prev.ancestor::<Mask<S>>(common) != path.ancestor::<Mask<S>>(common) {
+                    common -= 1;
+                }
+                common + 1
+            }
+            _ => 0
+        };
+        self.path = Some(path);
+
+        let mut mask = Mask::<S>::zero();
+        const_loop!(L in 0..{<LevelCount<S> as ConstInteger>::VALUE} => 'select: {
+            if (from..=N::VALUE).contains(&L) {
+                let level_index = path.ancestor::<Mask<S>>(L) & (Mask::<S>::SIZE - 1);
+                mask = self.cursor.select_level_node(src, ConstUsize::<L>, level_index);
+            }
+            if L == N::VALUE {
+                break 'select;
+            }
+        });
+        mask
+    }
+}
+
+pub struct Cursor<'src, S>
+where
+    S: Borrowable<Borrowed: HibitTree>,
+{
+    /// Shifted node covers one or two adjacent source nodes.
+    lo: SourceCursor<'src, S>,
+    hi: SourceCursor<'src, S>,
+    path: NodePath,
+    phantom: PhantomData<&'src Shift<S>>
+}
+
+impl<'this, 'src, S> HibitTreeCursorTypes<'this> for Cursor<'src, S>
+where
+    S: Borrowable<Borrowed: HibitTree>,
+{
+    type Data = <<S::Borrowed as HibitTreeTypes<'src>>::Cursor as HibitTreeCursorTypes<'this>>::Data;
+}
+
+impl<'src, S> Cursor<'src, S>
+where
+    S: Borrowable<Borrowed: HibitTree>,
+{
+    /// Source position of shifted terminal node element.
+    #[inline]
+    fn source_index(&self, this: &Shift<S>, level_index: usize) -> Option<(usize, usize)> {
+        let bits = Mask::<S>::SIZE.ilog2();
+        let index = (self.path.node << bits) + level_index;
+        let index = index.checked_sub(this.offset)?;
+        Some((index >> bits, index & (Mask::<S>::SIZE - 1)))
+    }
+}
+
+impl<'src, S> HibitTreeCursor<'src> for Cursor<'src, S>
+where
+    S: Borrowable<Borrowed: HibitTree>,
+{
+    type Src = Shift<S>;
+
+    #[inline]
+    fn new(this: &'src Self::Src) -> Self {
+        Self{
+            lo: SourceCursor{ cursor: HibitTreeCursor::new(this.s.borrow()), path: None },
+            hi: SourceCursor{ cursor: HibitTreeCursor::new(this.s.borrow()), path: None },
+            path: NodePath{ level: 0, node: 0 },
+            phantom: PhantomData
+        }
+    }
+
+    #[inline]
+    unsafe fn select_level_node<N: ConstInteger>(
+        &mut self, this: &'src Self::Src, level_n: N, level_index: usize
+    ) -> <Self::Src as HibitTree>::LevelMask {
+        let size = Mask::<S>::SIZE;
+        let node = if N::VALUE == 0 {
+            0
+        } else {
+            (self.path.ancestor::<Mask<S>>(N::VALUE - 1) << size.ilog2()) + level_index
+        };
+        self.path = NodePath{ level: N::VALUE, node };
+
+        let src = this.s.borrow();
+        let (q, r) = this.split_offset(N::VALUE);
+        if r == 0 {
+            // Aligned - whole source node re-rooted as is.
+            return if node >= q {
+                // Parent could have been `hi`.
+                let parent = (node - q) >> size.ilog2();
+                if N::VALUE != 0 && !self.lo.is_within(N::VALUE - 1, parent) && self.hi.is_within(N::VALUE - 1, parent) {
+                    mem::swap(&mut self.lo, &mut self.hi);
+                }
+                self.lo.select(src, level_n, node - q)
+            } else {
+                Mask::<S>::zero()
+            };
+        }
+
+        // Node spans the tail of `lo` and the head of `hi` source nodes.
+        let lo_mask = if node > q { self.lo.select(src, level_n, node - q - 1) } else { Mask::<S>::zero() };
+        let hi_mask = if node >= q { self.hi.select(src, level_n, node - q) } else { Mask::<S>::zero() };
+
+        // Child `i` of the shifted node starts within `lo ++ hi` child `first + i`,
+        // and spans the next one too, if children are not aligned.
+        let child_exp = Self::Src::span_exp(N::VALUE + 1);
+        let straddle = r & ((1 << child_exp) - 1) != 0;
+        let first = size - (r >> child_exp) - straddle as usize;
+
+        let mut mask = Mask::<S>::zero();
+        for i in lo_mask.into_bits_iter() {
+            if i >= first {
+                mask.set_bit::<true>(i - first);
+            }
+            if straddle && i > first {
+                mask.set_bit::<true>(i - first - 1);
+            }
+        }
+        for i in hi_mask.into_bits_iter() {
+            let i = size - first + i;
+            if straddle && i - 1 < size {
+                mask.set_bit::<true>(i - 1);
+            }
+            if i >= size {
+                break;
+            }
+            mask.set_bit::<true>(i);
+        }
+        mask
+    }
+
+    #[inline]
+    unsafe fn select_level_node_unchecked<N: ConstInteger>(
+        &mut self, this: &'src Self::Src, level_n: N, level_index: usize
+    ) -> <Self::Src as HibitTree>::LevelMask {
+        // Shifted node existence does not mean that both source nodes exist.
+        self.select_level_node(this, level_n, level_index)
+    }
+
+    #[inline]
+    unsafe fn data<'a>(&'a self, this: &'src Self::Src, level_index: usize)
+        -> Option<<Self as HibitTreeCursorTypes<'a>>::Data>
+    {
+        let (node, level_index) = self.source_index(this, level_index)?;
+        let terminal = LevelCount::<S>::VALUE - 1;
+        if self.lo.is_at(terminal, node) {
+            self.lo.cursor.data(this.s.borrow(), level_index)
+        } else if self.hi.is_at(terminal, node) {
+            self.hi.cursor.data(this.s.borrow(), level_index)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    unsafe fn data_unchecked<'a>(&'a self, this: &'src Self::Src, level_index: usize)
+        -> <Self as HibitTreeCursorTypes<'a>>::Data
+    {
+        let (node, level_index) = self.source_index(this, level_index).unwrap_unchecked();
+        let terminal = LevelCount::<S>::VALUE - 1;
+        let src = if self.lo.is_at(terminal, node) { &self.lo } else { &self.hi };
+        src.cursor.data_unchecked(this.s.borrow(), level_index)
+    }
+}
+
+impl<S> LazyHibitTree for Shift<S>
+where
+    Shift<S>: HibitTree
+{}
+
+impl<S> Borrowable for Shift<S>{ type Borrowed = Self; }
+
+impl_hibit_tree_cmp!([S] Shift<S>);
+
+/// Elements of `s`, with each index `i` moved to `i + offset`.
+///
+/// Elements that end up outside of [index_range] are dropped.
+///
+/// Nodes, whose span (`width^(depth - level)` indices) divides `offset`,
+/// are re-rooted as a whole. So for an offset aligned to a subtree span,
+/// everything below that subtree level costs the same as in `s` itself.
+/// Unaligned node is assembled from two adjacent source nodes.
+///
+/// ```
+/// # use hibit_tree::{intersection, shift, DenseTree, HibitTree};
+/// # use itertools::assert_equal;
+/// let global: DenseTree<&str, 4> = [(10, "a"), (4096 + 1, "b"), (4096 + 2, "c")].into_iter().collect();
+/// let shard : DenseTree<usize, 4> = [(1, 100), (2, 200), (3, 300)].into_iter().collect();
+///
+/// let i = intersection(&global, shift(&shard, 4096));
+/// assert_equal(i.iter(), [(4097, (&"b", &100)), (4098, (&"c", &200))]);
+/// ```
+///
+/// [index_range]: HibitTree::index_range
+#[inline]
+pub fn shift<S>(s: S, offset: usize) -> Shift<S>
+where
+    S: Borrowable<Borrowed: HibitTree>,
+{
+    Shift{ s, offset }
+}
+
+#[cfg(test)]
+mod tests{
+    use itertools::assert_equal;
+    use rand::{Rng, SeedableRng};
+    use crate::dense_tree::DenseTree;
+    use crate::hibit_tree::{HibitTree, LazyHibitTree, RegularHibitTree};
+    use crate::ops::intersection::intersection;
+    use crate::ops::shift::shift;
+
+    #[test]
+    fn smoke_test(){
+        type Array = DenseTree<usize, 3>;
+        let a: Array = [(0, 0), (63, 63), (64, 64), (5000, 5000), (200_000, 200_000)].into_iter().collect();
+
+        let s = shift(&a, 100);
+        assert_equal(s.iter(), [(100, &0), (163, &63), (164, &64), (5100, &5000), (200_100, &200_000)]);
+        assert_equal(s.range(150..6000).map(|(k, _)| k), [163, 164, 5100]);
+        assert_eq!(s.get(163), Some(&63));
+        assert_eq!(s.get(63), None);
+        assert_eq!(s.get(99), None);
+        assert!(s.contains(5100));
+        assert_eq!(s.last(), Some((200_100, &200_000)));
+
+        // Out of range elements dropped.
+        let s = shift(&a, Array::index_range().end - 64);
+        assert_equal(s.iter().map(|(k, _)| k), [Array::index_range().end - 64, Array::index_range().end - 1]);
+        assert!(shift(&a, usize::MAX).is_empty());
+
+        let m: Array = shift(&a, 64*64).map(|v: &usize| *v).materialize();
+        assert_equal(m.iter().map(|(k, _)| k), [4096, 4096 + 63, 4096 + 64, 4096 + 5000, 4096 + 200_000]);
+    }
+
+    #[test]
+    fn fuzzy_test(){
+        type Array = DenseTree<usize, 3>;
+        const RANGE: usize = 64*64*64;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0xb9db3dee3a0fe15b);
+        for _ in 0..20 {
+            let a: Array = (0..300).map(|_| { let k = rng.gen_range(0..RANGE); (k, k) }).collect();
+            let b: Array = (0..3000).map(|_| { let k = rng.gen_range(0..RANGE); (k, k) }).collect();
+            for offset in [0, 1, 63, 64, 65, 4095, 4096, 4097, 64*64*3 + 64*5 + 7, rng.gen_range(0..RANGE)] {
+                let expected: Vec<_> = a.iter()
+                    .map(|(k, v)| (k + offset, v))
+                    .filter(|(k, _)| *k < RANGE)
+                    .collect();
+                let s = shift(&a, offset);
+                assert_equal(s.iter(), expected.iter().copied());
+                assert_equal(s.iter().rev(), expected.iter().rev().copied());
+
+                let expected: Vec<_> = expected.into_iter().filter(|(k, _)| b.contains(*k)).collect();
+                let i = intersection(&b, &s);
+                assert_equal(i.iter().map(|(k, (_, v))| (k, v)), expected.iter().copied());
+            }
+        }
+    }
+}
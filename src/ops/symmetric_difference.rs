@@ -0,0 +1,234 @@
+use std::marker::PhantomData;
+use crate::const_utils::ConstInteger;
+use crate::{LazyHibitTree, HibitTreeCursorTypes, HibitTreeTypes};
+use crate::hibit_tree::{HibitTree, HibitTreeCursor};
+use crate::utils::{Borrowable, Either};
+use crate::cmp::impl_hibit_tree_cmp;
+
+/// Elements that are either in `S0` or in `S1`, but not in both.
+///
+/// Constructed by [symmetric_difference()].
+pub struct SymmetricDifference<S0, S1>{
+    s0: S0,
+    s1: S1,
+}
+
+impl<'this, S0, S1> HibitTreeTypes<'this> for SymmetricDifference<S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree<
+        LevelCount = <S0::Borrowed as HibitTree>::LevelCount,
+        LevelMask  = <S0::Borrowed as HibitTree>::LevelMask,
+    >>,
+{
+    type Data = Either<
+        <S0::Borrowed as HibitTreeTypes<'this>>::Data,
+        <S1::Borrowed as HibitTreeTypes<'this>>::Data
+    >;
+
+    type DataUnchecked = Self::Data;
+
+    type Cursor = Cursor<'this, S0, S1>;
+}
+
+impl<S0, S1> HibitTree for SymmetricDifference<S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree<
+        LevelCount = <S0::Borrowed as HibitTree>::LevelCount,
+        LevelMask  = <S0::Borrowed as HibitTree>::LevelMask,
+    >>,
+{
+    /// Intermediate node may contain only common elements.
+    const EXACT_HIERARCHY: bool = false;
+
+    type LevelCount = <S0::Borrowed as HibitTree>::LevelCount;
+    type LevelMask  = <S0::Borrowed as HibitTree>::LevelMask;
+
+    #[inline]
+    unsafe fn data(&self, index: usize, level_indices: &[usize])
+        -> Option<<Self as HibitTreeTypes<'_>>::Data>
+    {
+        let d0 = self.s0.borrow().data(index, level_indices);
+        let d1 = self.s1.borrow().data(index, level_indices);
+        match (d0, d1) {
+            (Some(d0), None) => Some(Either::Left(d0)),
+            (None, Some(d1)) => Some(Either::Right(d1)),
+            _ => None
+        }
+    }
+
+    #[inline]
+    unsafe fn data_unchecked(&self, index: usize, level_indices: &[usize])
+        -> <Self as HibitTreeTypes<'_>>::Data
+    {
+        self.data(index, level_indices).unwrap_unchecked()
+    }
+}
+
+pub struct Cursor<'src, S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree>,
+{
+    s0: <S0::Borrowed as HibitTreeTypes<'src>>::Cursor,
+    s1: <S1::Borrowed as HibitTreeTypes<'src>>::Cursor,
+    phantom: PhantomData<&'src SymmetricDifference<S0, S1>>
+}
+
+impl<'this, 'src, S0, S1> HibitTreeCursorTypes<'this> for Cursor<'src, S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree>,
+{
+    type Data = Either<
+        <<S0::Borrowed as HibitTreeTypes<'src>>::Cursor as HibitTreeCursorTypes<'this>>::Data,
+        <<S1::Borrowed as HibitTreeTypes<'src>>::Cursor as HibitTreeCursorTypes<'this>>::Data
+    >;
+}
+
+impl<'src, S0, S1> HibitTreeCursor<'src> for Cursor<'src, S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree<
+        LevelCount = <S0::Borrowed as HibitTree>::LevelCount,
+        LevelMask  = <S0::Borrowed as HibitTree>::LevelMask,
+    >>
+{
+    type Src = SymmetricDifference<S0, S1>;
+
+    #[inline]
+    fn new(src: &'src Self::Src) -> Self {
+        Self{
+            s0: HibitTreeCursor::new(src.s0.borrow()),
+            s1: HibitTreeCursor::new(src.s1.borrow()),
+            phantom: PhantomData
+        }
+    }
+
+    #[inline]
+    unsafe fn select_level_node<N: ConstInteger>(
+        &mut self, this: &'src Self::Src, level_n: N, level_index: usize
+    ) -> <Self::Src as HibitTree>::LevelMask {
+        // unchecked version already deal with non-existent elements
+        self.select_level_node_unchecked(this, level_n, level_index)
+    }
+
+    #[inline]
+    unsafe fn select_level_node_unchecked<N: ConstInteger> (
+        &mut self, this: &'src Self::Src, level_n: N, level_index: usize
+    ) -> <Self::Src as HibitTree>::LevelMask {
+        let mask0 = self.s0.select_level_node(
+            this.s0.borrow(), level_n, level_index,
+        );
+
+        let mask1 = self.s1.select_level_node(
+            this.s1.borrow(), level_n, level_index,
+        );
+
+        // Intermediate bit set in both trees does not mean, that both 
+        // subtrees have the same elements - each may have elements the other
+        // one does not. XOR would drop such subtree, with all its unique
+        // elements. So intermediate levels are OR'ed - any subtree where 
+        // at least one tree has something may contribute. If it turns out
+        // that all its elements are common - node ends up empty, hence
+        // no EXACT_HIERARCHY. Terminal level bit is a single element -
+        // exact, so XOR there is precisely "in exactly one of two".
+        if N::VALUE == <S0::Borrowed as HibitTree>::LevelCount::VALUE - 1 {
+            mask0 ^ mask1
+        } else {
+            mask0 | mask1
+        }
+    }
+
+    #[inline]
+    unsafe fn data<'a>(&'a self, this: &'src Self::Src, level_index: usize)
+        -> Option<<Self as HibitTreeCursorTypes<'a>>::Data>
+    {
+        let d0 = self.s0.data(this.s0.borrow(), level_index);
+        let d1 = self.s1.data(this.s1.borrow(), level_index);
+        match (d0, d1) {
+            (Some(d0), None) => Some(Either::Left(d0)),
+            (None, Some(d1)) => Some(Either::Right(d1)),
+            _ => None
+        }
+    }
+
+    #[inline]
+    unsafe fn data_unchecked<'a>(&'a self, this: &'src Self::Src, level_index: usize)
+        -> <Self as HibitTreeCursorTypes<'a>>::Data
+    {
+        // Terminal mask guarantees that exactly one exists.
+        if let Some(d0) = self.s0.data(this.s0.borrow(), level_index) {
+            Either::Left(d0)
+        } else {
+            Either::Right(self.s1.data_unchecked(this.s1.borrow(), level_index))
+        }
+    }
+}
+
+impl<S0, S1> LazyHibitTree for SymmetricDifference<S0, S1>
+where
+    SymmetricDifference<S0, S1>: HibitTree
+{}
+
+impl<S0, S1> Borrowable for SymmetricDifference<S0, S1>{ type Borrowed = Self; }
+
+impl_hibit_tree_cmp!([S0, S1] SymmetricDifference<S0, S1>);
+
+/// Elements that are in exactly one of `s0`, `s1`.
+///
+/// Data is [Either] `s0` or `s1` element.
+#[inline]
+pub fn symmetric_difference<S0, S1>(s0: S0, s1: S1) -> SymmetricDifference<S0, S1>
+where
+    S0: Borrowable<Borrowed: HibitTree>,
+    S1: Borrowable<Borrowed: HibitTree<
+        LevelCount = <S0::Borrowed as HibitTree>::LevelCount,
+        LevelMask  = <S0::Borrowed as HibitTree>::LevelMask,
+    >>
+{
+    SymmetricDifference { s0, s1 }
+}
+
+#[cfg(test)]
+mod tests{
+    use itertools::assert_equal;
+    use crate::dense_tree::DenseTree;
+    use crate::ops::intersection::intersection;
+    use crate::ops::symmetric_difference::symmetric_difference;
+    use crate::hibit_tree::HibitTree;
+    use crate::utils::Either::{Left, Right};
+
+    #[test]
+    fn smoke_test(){
+        type Array = DenseTree<usize, 3>;
+        let a: Array = [(10, 10), (15, 15), (200, 200), (70_000, 70_000)].into_iter().collect();
+        let b: Array = [(15, 15), (100, 100), (70_000, 70_000)].into_iter().collect();
+
+        let diff = symmetric_difference(&a, &b);
+        assert_equal(diff.iter(), [(10, Left(&10)), (100, Right(&100)), (200, Left(&200))]);
+        assert_eq!(diff.get(100), Some(Right(&100)));
+        assert_eq!(diff.get(15), None);
+        assert_eq!(diff.get(16), None);
+        assert_eq!(diff.last().map(|(k, _)| k), Some(200));
+
+        assert!(symmetric_difference(&a, &a).is_empty());
+        assert_equal(symmetric_difference(&a, &Array::default()).iter().map(|(k, _)| k), [10, 15, 200, 70_000]);
+    }
+
+    #[test]
+    fn non_exact_test(){
+        type Array = DenseTree<usize, 3>;
+        let a: Array = (0..5000).step_by(2).map(|k| (k, k)).collect();
+        let b: Array = (0..5000).step_by(3).map(|k| (k, k)).collect();
+        let c: Array = (0..5000).step_by(5).map(|k| (k, k)).collect();
+
+        let ab = intersection(&a, &b);
+        let diff = symmetric_difference(&ab, &c);
+        assert_equal(
+            diff.iter().map(|(k, _)| k),
+            (0..5000).filter(|k| (k % 6 == 0) != (k % 5 == 0))
+        );
+    }
+}
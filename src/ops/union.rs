@@ -6,6 +6,7 @@ use crate::hibit_tree::{HibitTree, HibitTreeCursor};
 use crate::{BitBlock, LazyHibitTree, HibitTreeCursorTypes, HibitTreeTypes};
 use crate::bit_queue::BitQueue;
 use crate::utils::{Array, Borrowable};
+use crate::cmp::impl_hibit_tree_cmp;
 
 pub struct Union<S0, S1>{
     s0: S0,
@@ -168,6 +169,8 @@ where
 
 impl<S0, S1> Borrowable for Union<S0, S1>{ type Borrowed = Self; }
 
+impl_hibit_tree_cmp!([S0, S1] Union<S0, S1>);
+
 #[inline]
 pub fn union<S0, S1>(s0: S0, s1: S1) -> Union<S0, S1>
 where
@@ -0,0 +1,140 @@
+use crate::{HibitTree, HibitTreeTypes, RegularHibitTree};
+use crate::ops::map::MapFunction;
+use crate::ops::{Map, Union};
+use crate::utils::{BinaryFunction, Borrowable, UnaryFunction};
+
+/// [union] with each element resolved by one of three functions.
+///
+/// Constructed by [union_with()].
+///
+/// [union]: crate::union
+pub type UnionWith<S0, S1, L, R, B> = Map<Union<S0, S1>, UnionResolvers<L, R, B>>;
+
+/// Maps [Union] item `(Option<D0>, Option<D1>)` to output of one of the functions.
+///
+/// Used by [UnionWith].
+#[derive(Clone)]
+pub struct UnionResolvers<L, R, B>{
+    left_only : L,
+    right_only: R,
+    both      : B,
+}
+
+impl<D0, D1, O, L, R, B> UnaryFunction<(Option<D0>, Option<D1>)> for UnionResolvers<L, R, B>
+where
+    L: UnaryFunction<D0, Output = O>,
+    R: UnaryFunction<D1, Output = O>,
+    B: BinaryFunction<D0, D1, Output = O>,
+{
+    type Output = O;
+
+    #[inline]
+    fn exec(&self, arg: (Option<D0>, Option<D1>)) -> O {
+        match arg {
+            (Some(d0), Some(d1)) => self.both.exec(d0, d1),
+            (Some(d0), None) => self.left_only.exec(d0),
+            (None, Some(d1)) => self.right_only.exec(d1),
+            // Union never produces an item without data.
+            (None, None) => unreachable!()
+        }
+    }
+}
+
+/// Union of `s0` and `s1`, where each element resolved to a single value,
+/// like outer join.
+///
+/// - `left_only(D0) -> O` - for elements that exist only in `s0`.
+/// - `right_only(D1) -> O` - for elements that exist only in `s1`.
+/// - `both(D0, D1) -> O` - for elements that exist in both.
+///
+/// Result is [RegularHibitTree] with `O` data.
+///
+/// Same as with [map], you'll have to specify lambda argument types:
+///
+/// ```
+/// # use hibit_tree::{union_with, DenseTree, HibitTree, LazyHibitTree};
+/// # use itertools::assert_equal;
+/// let a: DenseTree<f32, 4> = [(1, 1.0), (2, 2.0)].into_iter().collect();
+/// let b: DenseTree<f32, 4> = [(2, 0.5), (3, 3.0)].into_iter().collect();
+///
+/// let sum: DenseTree<f32, 4> = union_with(&a, &b,
+///     |a: &f32| *a,
+///     |b: &f32| *b,
+///     |a: &f32, b: &f32| a + b
+/// ).materialize();
+/// assert_equal(sum.iter(), [(1, &1.0), (2, &2.5), (3, &3.0)]);
+/// ```
+///
+/// [map]: crate::map
+#[inline]
+pub fn union_with<S0, S1, L, R, B>(s0: S0, s1: S1, left_only: L, right_only: R, both: B)
+    -> UnionWith<S0, S1, L, R, B>
+where
+    S0: Borrowable<Borrowed: RegularHibitTree>,
+    S1: Borrowable<Borrowed: RegularHibitTree<
+        LevelCount = <S0::Borrowed as HibitTree>::LevelCount,
+        LevelMask  = <S0::Borrowed as HibitTree>::LevelMask,
+    >>,
+    Union<S0, S1>: RegularHibitTree,
+    UnionResolvers<L, R, B>: for<'a> MapFunction<'a, <Union<S0, S1> as HibitTreeTypes<'a>>::Data>
+{
+    crate::map(
+        crate::union(s0, s1),
+        UnionResolvers{ left_only, right_only, both }
+    )
+}
+
+#[cfg(test)]
+mod tests{
+    use itertools::assert_equal;
+    use crate::dense_tree::DenseTree;
+    use crate::hibit_tree::{HibitTree, LazyHibitTree};
+    use crate::ops::intersection::intersection;
+    use crate::ops::union_with::union_with;
+
+    #[test]
+    fn smoke_test(){
+        type Array = DenseTree<usize, 3>;
+        let a: Array = [(10, 10), (15, 15), (200, 200)].into_iter().collect();
+        let b: Array = [(15, 1), (100, 100), (70_000, 70_000)].into_iter().collect();
+
+        let u = union_with(&a, &b,
+            |v: &usize| *v,
+            |v: &usize| 2 * v,
+            |v0: &usize, v1: &usize| v0 - v1
+        );
+        assert_equal(u.iter(), [(10, 10), (15, 14), (100, 200), (200, 200), (70_000, 140_000)]);
+        assert_eq!(u.get(15), Some(14));
+        assert_eq!(u.get(16), None);
+
+        // Non-exact source.
+        let ab = intersection(&a, &b);
+        let u = union_with(&ab, &a,
+            |(v0, _): (&usize, &usize)| *v0,
+            |v: &usize| *v + 1,
+            |_: (&usize, &usize), _: &usize| 0
+        );
+        let m: Array = u.materialize();
+        assert_equal(m.iter(), [(10, &11), (15, &0), (200, &201)]);
+    }
+
+    #[test]
+    fn sparse_vector_sum_test(){
+        type Vector = DenseTree<f32, 4>;
+        let a: Vector = (0..1000).step_by(2).map(|k| (k, k as f32)).collect();
+        let b: Vector = (0..1000).step_by(3).map(|k| (k, 1.0)).collect();
+
+        let sum: Vector = union_with(&a, &b, |a: &f32| *a, |b: &f32| *b, |a: &f32, b: &f32| a + b)
+            .materialize();
+        assert_equal(
+            sum.iter().map(|(k, v)| (k, *v)),
+            (0..1000)
+                .filter(|k| k % 2 == 0 || k % 3 == 0)
+                .map(|k| {
+                    let a = if k % 2 == 0 { k as f32 } else { 0.0 };
+                    let b = if k % 3 == 0 { 1.0 } else { 0.0 };
+                    (k, a + b)
+                })
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Node pruning for containers' in-place operations.
+
+use crate::BitBlock;
+use crate::const_utils::ConstInteger;
+use crate::hibit_tree::{HibitTree, HibitTreeCursor};
+use crate::HibitTreeTypes;
+
+/// Decides which children of container node to keep, before visiting them.
+///
+/// Visited depth-first, in key order.
+pub(crate) trait Prune<Mask> {
+    /// Enter container node at level `n`, which is `level_index` child of the
+    /// previously entered node.
+    ///
+    /// Returns `(keep, descend)`:
+    /// - `mask` children not in `keep` removed with whole subtree.
+    /// - `descend` children (subset of `keep`) entered with `self`.
+    /// - All other `keep` children kept with whole subtree.
+    ///
+    /// For terminal level, only `keep` matters.
+    unsafe fn enter<N: ConstInteger>(&mut self, n: N, level_index: usize, mask: Mask) -> (Mask, Mask);
+}
+
+/// Keep everything.
+pub(crate) struct KeepAll;
+impl<Mask: BitBlock> Prune<Mask> for KeepAll {
+    #[inline(always)]
+    unsafe fn enter<N: ConstInteger>(&mut self, _: N, _: usize, mask: Mask) -> (Mask, Mask) {
+        (mask, Mask::zero())
+    }
+}
+
+/// Remove everything.
+pub(crate) struct RemoveAll;
+impl<Mask: BitBlock> Prune<Mask> for RemoveAll {
+    #[inline(always)]
+    unsafe fn enter<N: ConstInteger>(&mut self, _: N, _: usize, _: Mask) -> (Mask, Mask) {
+        (Mask::zero(), Mask::zero())
+    }
+}
+
+/// Keep only elements that are in `other`.
+pub(crate) struct Intersect<'a, O: HibitTree>{
+    other: &'a O,
+    cursor: <O as HibitTreeTypes<'a>>::Cursor,
+}
+
+impl<'a, O: HibitTree> Intersect<'a, O>{
+    #[inline]
+    pub fn new(other: &'a O) -> Self {
+        Self{ other, cursor: HibitTreeCursor::new(other) }
+    }
+}
+
+impl<'a, O: HibitTree> Prune<O::LevelMask> for Intersect<'a, O> {
+    #[inline]
+    unsafe fn enter<N: ConstInteger>(&mut self, n: N, level_index: usize, mask: O::LevelMask)
+        -> (O::LevelMask, O::LevelMask)
+    {
+        let other_mask = self.cursor.select_level_node(self.other, n, level_index);
+        // Raised bit of non-exact `other` may still lead to an empty node -
+        // so we descend into all common children.
+        let keep = mask & other_mask;
+        (keep.clone(), keep)
+    }
+}
+
+/// Keep only elements that are not in `other`.
+pub(crate) struct Subtract<'a, O: HibitTree>{
+    other: &'a O,
+    cursor: <O as HibitTreeTypes<'a>>::Cursor,
+}
+
+impl<'a, O: HibitTree> Subtract<'a, O>{
+    #[inline]
+    pub fn new(other: &'a O) -> Self {
+        Self{ other, cursor: HibitTreeCursor::new(other) }
+    }
+}
+
+impl<'a, O: HibitTree> Prune<O::LevelMask> for Subtract<'a, O> {
+    #[inline]
+    unsafe fn enter<N: ConstInteger>(&mut self, n: N, level_index: usize, mask: O::LevelMask)
+        -> (O::LevelMask, O::LevelMask)
+    {
+        let other_mask = self.cursor.select_level_node(self.other, n, level_index);
+        if N::VALUE == O::LevelCount::VALUE - 1 {
+            // Terminal masks are always exact.
+            (mask & !other_mask, O::LevelMask::zero())
+        } else {
+            // Subtrees not in `other` kept as is.
+            let descend = mask.clone() & other_mask;
+            (mask, descend)
+        }
+    }
+}
@@ -11,11 +11,12 @@ impl<const B: bool> DefaultRequirement for ReqDefault<B>{
     const REQUIRED: bool = B;
 } 
 
-pub(crate) trait DefaultInit {
+/// Not reachable outside crate - `pub` only to be usable in public bounds.
+pub trait DefaultInit {
     unsafe fn init_default(value: *mut u8);
 }
 
-pub(crate) struct DefaultInitFor<T, R>(PhantomData<(T, R)>);
+pub struct DefaultInitFor<T, R>(PhantomData<(T, R)>);
 impl<T: Default> DefaultInit for DefaultInitFor<T, ReqDefault> {
     #[inline]
     unsafe fn init_default(value: *mut u8) {
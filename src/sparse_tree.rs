@@ -1,3 +1,5 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::ops::ControlFlow;
 use std::ops::ControlFlow::{Break, Continue};
@@ -14,8 +16,18 @@ use crate::{Empty, Index, HibitTreeCursorTypes, HibitTreeTypes};
 use crate::req_default::{DefaultInit, DefaultInitFor, DefaultRequirement, ReqDefault};
 use crate::utils::Primitive;
 use crate::utils::Array;
+use crate::alloc_vec::AllocVec;
 use crate::sparse_tree_levels::{FoldMutVisitor, FoldVisitor, MutVisitor, SparseTreeLevels, TypeVisitor, Visitor};
 use crate::hibit_tree::{HibitTree, HibitTreeCursor};
+use crate::cmp::impl_hibit_tree_cmp;
+
+mod entry;
+mod retain;
+mod set_ops;
+mod from;
+mod memory_stats;
+
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
 
 /// Uncompressed Hierarchical Bitmap Tree.
 ///
@@ -44,6 +56,15 @@ use crate::hibit_tree::{HibitTree, HibitTreeCursor};
 ///
 /// [get_or_default]: SparseTree::get_or_default 
 /// [get_unchecked]: SparseTree::get_unchecked
+///
+/// # Allocator
+///
+/// Level blocks, elements and keys are allocated with `Levels` [NodeAllocator]
+/// (see [config]). Allocator with state is passed with [new_in()].
+///
+/// [NodeAllocator]: crate::NodeAllocator
+/// [config]: crate::config
+/// [new_in()]: SparseTree::new_in
 pub struct SparseTree<Levels, Data, R = ReqDefault<false>>
 where
     Levels: SparseTreeLevels,
@@ -54,13 +75,13 @@ where
     // TODO: some kind of multi-vec, to reduce allocation count?
     
     /// First item - is a placeholder for non-existent/default element.
-    values: Vec<Data>,
-    keys  : Vec<usize>,
+    values: AllocVec<Data, Levels::Allocator>,
+    keys  : AllocVec<usize, Levels::Allocator>,
     
     // TODO: can be pair of u32's
     // Used only in remove().
     /// Coordinates in last level of pointer to value with this vec index.  
-    last_level_block_indices: Vec<(usize/*block_index*/, usize/*in-block index*/)>,
+    last_level_block_indices: AllocVec<(usize/*block_index*/, usize/*in-block index*/), Levels::Allocator>,
     
     phantom_data: PhantomData<R>
 }
@@ -68,29 +89,56 @@ where
 impl<Levels, Data, R> Default for
     SparseTree<Levels, Data, R>
 where
-    Levels: SparseTreeLevels,
+    Levels: SparseTreeLevels<Allocator: Default>,
     R: DefaultRequirement,
     DefaultInitFor<Data, R>: DefaultInit
 {
     #[inline]
     fn default() -> Self {
-        let mut values: Vec<Data> = Vec::with_capacity(1);
-        unsafe{ values.set_len(1); }
+        Self::new_in(Default::default())
+    }
+}
+
+impl<Levels, Data, R> SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+    DefaultInitFor<Data, R>: DefaultInit
+{
+    /// Constructs an empty tree, with level blocks and elements allocated
+    /// by `allocator`.
+    #[inline]
+    pub fn new_in(allocator: Levels::Allocator) -> Self {
+        let mut values: AllocVec<Data, _> = unsafe{ AllocVec::with_placeholder_in(1, allocator.clone()) };
         unsafe{
             <DefaultInitFor<Data, R> as DefaultInit>
             ::init_default(values.as_mut_ptr().cast());
         }
+        let mut keys = AllocVec::new_in(allocator.clone());
+        keys.push(usize::MAX);
+        let mut last_level_block_indices = AllocVec::new_in(allocator.clone());
+        last_level_block_indices.push((0,0));
         
         Self{
-            levels: Levels::default(),
+            levels: Levels::new_in(allocator),
             
             values, 
-            keys  : vec![usize::MAX],
-            last_level_block_indices: vec![(0,0)],
+            keys,
+            last_level_block_indices,
             
             phantom_data: PhantomData
         }
     }
+    
+    /// [with_capacity()] with level blocks and elements allocated by `allocator`.
+    /// 
+    /// [with_capacity()]: Self::with_capacity
+    #[inline]
+    pub fn with_capacity_in(capacity: usize, allocator: Levels::Allocator) -> Self {
+        let mut this = Self::new_in(allocator);
+        this.reserve(capacity);
+        this
+    }
 }
 
 struct BlockPtr<Levels, LevelN>(NonNull<u8>, PhantomData<*mut (Levels, LevelN)>);
@@ -181,6 +229,52 @@ where
         }
         Levels::visit_type(LevelN::default(), V(self.0.as_ptr(), index, item))        
     }
+    
+    /// # Safety
+    /// 
+    /// Child at `index` must exist.
+    #[inline(always)]
+    pub unsafe fn remove_child(self, index: usize){
+        struct V(*mut u8, usize);
+        impl<M> TypeVisitor<M> for V{
+            type Out = ();
+            
+            #[inline(always)]
+            fn visit<L>(self, _: PhantomData<L>) -> Self::Out 
+            where 
+                L: ILevel<Block: HiBlock> 
+            {
+                unsafe{
+                    let block = self.0 as *mut L::Block;
+                    (*block).remove_unchecked(self.1);
+                }
+            }
+        }
+        Levels::visit_type(LevelN::default(), V(self.0.as_ptr(), index))
+    }
+    
+    /// # Safety
+    /// 
+    /// Child at `index` must exist. `item` must be non-zero.
+    #[inline(always)]
+    pub unsafe fn set_child(self, index: usize, item: usize){
+        struct V(*mut u8, usize, usize);
+        impl<M> TypeVisitor<M> for V{
+            type Out = ();
+            
+            #[inline(always)]
+            fn visit<L>(self, _: PhantomData<L>) -> Self::Out 
+            where 
+                L: ILevel<Block: HiBlock> 
+            {
+                unsafe{
+                    let block = self.0 as *mut L::Block;
+                    (*block).set_unchecked(self.1, Primitive::from_usize(self.2));
+                }
+            }
+        }
+        Levels::visit_type(LevelN::default(), V(self.0.as_ptr(), index, item))
+    }
 }
 
 impl<Levels, Data, R> SparseTree<Levels, Data, R>
@@ -286,6 +380,59 @@ where
             return None;
         }
         
+        Some(unsafe{ self.remove_existing(level_indices, levels_block_indices, data_block_index) })
+    }
+    
+    /// Element with the smallest index. Same as [first()].
+    /// 
+    /// [first()]: HibitTree::first
+    #[inline]
+    pub fn first_key_value(&self) -> Option<(usize, &Data)> {
+        self.first()
+    }
+    
+    /// Element with the largest index. Same as [last()].
+    /// 
+    /// [last()]: HibitTree::last
+    #[inline]
+    pub fn last_key_value(&self) -> Option<(usize, &Data)> {
+        self.last()
+    }
+    
+    /// Remove and return the element with the smallest index.
+    /// 
+    /// Emptied nodes are removed, just like with [remove()].
+    /// 
+    /// [remove()]: Self::remove
+    #[inline]
+    pub fn pop_first(&mut self) -> Option<(usize, Data)> {
+        let (index, _) = self.first()?;
+        self.remove(index).map(|value| (index, value))
+    }
+    
+    /// Remove and return the element with the largest index.
+    /// 
+    /// Emptied nodes are removed, just like with [remove()].
+    /// 
+    /// [remove()]: Self::remove
+    #[inline]
+    pub fn pop_last(&mut self) -> Option<(usize, Data)> {
+        let (index, _) = self.last()?;
+        self.remove(index).map(|value| (index, value))
+    }
+    
+    /// # Safety
+    /// 
+    /// Element must exist. `levels_block_indices` and `data_block_index` - 
+    /// as returned by [fetch_block_indices] for `level_indices`.
+    /// 
+    /// [fetch_block_indices]: Self::fetch_block_indices
+    unsafe fn remove_existing(
+        &mut self,
+        level_indices: ConstCopyArrayType<usize, Levels::LevelCount>,
+        levels_block_indices: ConstCopyArrayType<usize, Levels::LevelCount>,
+        data_block_index: usize
+    ) -> Data {
         // 1. Update level masks
         self.levels.fold_rev_mut((), V{level_indices, levels_block_indices});
         struct V<LI, LBI>{
@@ -360,9 +507,7 @@ where
         
         // 2. Remove data        
         self.keys.swap_remove(data_block_index);
-        let value = self.values.swap_remove(data_block_index);
-        
-        Some(value)
+        self.values.swap_remove(data_block_index)
     }
     
     /// Returns mutable reference to item at `index`, if exists.
@@ -390,76 +535,36 @@ where
         self.get_or_insert_impl(index, ConstTrue, ||value);
     }
     
+    /// Returns new block index.
+    #[inline(always)]
+    fn insert_empty_block(&mut self, level_n: impl ConstInteger) -> usize {
+        struct Insert;
+        impl<M> MutVisitor<M> for Insert {
+            type Out = usize;
+            
+            #[inline(always)]
+            fn visit<I:ConstInteger, L: ILevel>(self, _: I, level: &mut L) -> usize {
+                level.insert_empty_block()
+            }
+        }
+        self.levels.visit_mut(level_n, Insert)
+    }
+    
     /// insert = true - will write value.
     #[inline]
     fn get_or_insert_impl(&mut self, index: usize, insert: impl ConstBool, value_fn: impl FnOnce() -> Data)
         -> &mut Data 
     {
-        let level_indices = crate::level_indices::<Levels::Mask, Levels::LevelCount>(index);
-        let last_level_inner_index = unsafe{ *level_indices.as_ref().last().unwrap_unchecked() };
-        
-        let mut level_block_index = 0;
-        const_loop!(LEVEL_INDEX in 0..{<Levels::LevelCount as ConstInteger>::Dec::VALUE} => {
-            let level_index = ConstUsize::<LEVEL_INDEX>;
-            let inner_index = level_indices.as_ref()[LEVEL_INDEX];
-            let next_level_block_index = unsafe {
-                let block = unsafe{ self.get_block(level_index, level_block_index) };
-                block.get_child(inner_index) 
-            };
-            level_block_index = if next_level_block_index.is_zero() {
-                // 1. Insert new block in next level
-                struct Insert;
-                impl<M> MutVisitor<M> for Insert {
-                    type Out = usize;
-                    
-                    #[inline(always)]
-                    fn visit<I:ConstInteger, L: ILevel>(self, _: I, level: &mut L) -> usize {
-                        level.insert_empty_block()
-                    }
-                }
-                let new_level_block_index = self.levels.visit_mut(level_index.inc(), Insert);
-                
-                // 2. Insert new block index as a child
-                unsafe{
-                    // Take **THE SAME** and **UNCHANGED** block again, now as mut, 
-                    // just because MIRI does not see that we work with different
-                    // level/tuple elements.
-                    // Let's hope that compiler does eliminate this inefficiency.
-                    let block_ptr = self.get_block_mut(level_index, level_block_index);   
-                    block_ptr.insert_child(inner_index, new_level_block_index);
-                }
-                
-                new_level_block_index
-            } else {
-                next_level_block_index
-            };
-        });
-        
-        // 3. Last level
-        let data_block_index = unsafe {
-            let mut block = self.get_block_mut(Levels::LevelCount::default().dec(), level_block_index);
-            let mut data_block_index = block.get_child(last_level_inner_index);
-            if data_block_index.is_zero() {
-                let i = self.values.len();
-                
-                self.values.push((value_fn)());
-                self.keys.push(index);
-                self.last_level_block_indices.push(
-                    (level_block_index, last_level_inner_index)
-                );
-                
-                block.insert_child(last_level_inner_index, i);
-                i                   
-            } else {
+        match self.entry_impl(index) {
+            Entry::Occupied(entry) => {
+                let value = entry.into_mut();
                 /*const*/ if insert.value() { 
-                    *self.values.get_unchecked_mut(data_block_index) = (value_fn)();
+                    *value = (value_fn)();
                 }
-                data_block_index
+                value
             }
-        };
-
-        // 4. Data
-        unsafe{ self.values.get_unchecked_mut(data_block_index) }  
+            Entry::Vacant(entry) => entry.insert((value_fn)())
+        }
     }
     
     /// Returns `Some`, if element with `index` exists in container.
@@ -493,6 +598,66 @@ where
         self.values.get_unchecked_mut(data_block_index)*/
     }
     
+    /// Number of elements.
+    #[inline]
+    pub fn len(&self) -> usize {
+        // skip first element
+        self.keys.len() - 1
+    }
+    
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Level blocks and elements allocator.
+    #[inline]
+    pub fn allocator(&self) -> &Levels::Allocator {
+        self.keys.allocator()
+    }
+    
+    /// Remove all elements.
+    /// 
+    /// Keeps allocated `values` capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.levels = Levels::new_in(self.allocator().clone());
+        
+        // drop values, except first placeholder/default
+        self.keys.truncate(1);
+        self.last_level_block_indices.truncate(1);
+        self.values.truncate(1);
+    }
+    
+    /// Number of elements tree can hold without reallocating elements storage.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        // skip first element
+        self.values.capacity()
+            .min(self.keys.capacity())
+            .min(self.last_level_block_indices.capacity()) - 1
+    }
+    
+    /// Reserve space for at least `additional` more elements.
+    /// 
+    /// Level blocks are not reserved.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.values.reserve(additional);
+        self.keys.reserve(additional);
+        self.last_level_block_indices.reserve(additional);
+    }
+    
+    /// Shrink elements storage as much as possible.
+    /// 
+    /// Level blocks are kept - empty ones are reused by insert.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.values.shrink_to_fit();
+        self.keys.shrink_to_fit();
+        self.last_level_block_indices.shrink_to_fit();
+    }
+    
     // TODO: KeyValues type
     /// Key-values in arbitrary order.
     #[inline]
@@ -518,6 +683,16 @@ where
         }
     }
     
+    /// Forget all values, as if they were moved out.
+    /// 
+    /// # Safety
+    /// 
+    /// Tree is left in inconsistent state - it must be dropped right after.
+    #[inline]
+    pub(crate) unsafe fn forget_values(&mut self) {
+        self.values.set_len(1);
+    }
+    
     #[inline]
     unsafe fn drop_impl(&mut self){
         // Manually drop values, skipping first non-existent element, if necessary.
@@ -531,6 +706,127 @@ where
     }
 }
 
+impl<Levels, Data, R> SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+    Self: Default
+{
+    /// Constructs an empty tree with space for at least `capacity` elements.
+    /// 
+    /// Only elements storage is preallocated - level blocks are allocated on insert.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut this = Self::default();
+        this.reserve(capacity);
+        this
+    }
+    
+    /// Construct from `(index, value)` pairs, sorted by index.
+    /// 
+    /// Unlike [FromIterator], which [insert]s elements one by one, each key
+    /// descends only from the level where its path diverges from the
+    /// previous key's. 
+    /// 
+    /// # Panics
+    /// 
+    /// If indices are not in strictly ascending order, or out of [index_range].
+    /// 
+    /// [insert]: Self::insert
+    /// [index_range]: HibitTree::index_range
+    pub fn from_sorted_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (usize, Data)>
+    {
+        let iter = iter.into_iter();
+        let additional = iter.size_hint().0;
+        let mut this = Self::with_capacity(additional);
+        
+        // Block indices along the previous key path.
+        let mut path_block_indices: ConstCopyArrayType<usize, Levels::LevelCount> = Array::from_fn(|_|0);
+        let mut prev_index: Option<usize> = None;
+        for (index, value) in iter {
+            let index: usize = Index::<Levels::Mask, Levels::LevelCount>::from(index).into();
+            let level_indices = crate::level_indices::<Levels::Mask, Levels::LevelCount>(index);
+            
+            // Blocks above diverge level are shared with previous key. 
+            // And since keys are ascending - diverge level block
+            // does not have a child for this key yet.
+            let diverge_level = if let Some(prev_index) = prev_index {
+                assert!(prev_index < index, "Keys must be in strictly ascending order.");
+                let prev_level_indices = crate::level_indices::<Levels::Mask, Levels::LevelCount>(prev_index);
+                (0..Levels::LevelCount::VALUE)
+                    .position(|n| level_indices.as_ref()[n] != prev_level_indices.as_ref()[n])
+                    .unwrap()
+            } else {
+                0
+            };
+            
+            const_loop!(LEVEL_INDEX in 0..{<Levels::LevelCount as ConstInteger>::Dec::VALUE} => {
+                let level_index = ConstUsize::<LEVEL_INDEX>;
+                if level_index.value() >= diverge_level {
+                    let new_level_block_index = this.insert_empty_block(level_index.inc());
+                    unsafe{
+                        let block_ptr = this.get_block_mut(level_index, path_block_indices.as_ref()[LEVEL_INDEX]);
+                        block_ptr.insert_child(level_indices.as_ref()[LEVEL_INDEX], new_level_block_index);
+                    }
+                    path_block_indices.as_mut()[LEVEL_INDEX + 1] = new_level_block_index;
+                }
+            });
+            
+            // Last level
+            let level_block_index = unsafe{ *path_block_indices.as_ref().last().unwrap_unchecked() };
+            let inner_index = unsafe{ *level_indices.as_ref().last().unwrap_unchecked() };
+            let i = this.values.len();
+            this.values.push(value);
+            this.keys.push(index);
+            this.last_level_block_indices.push((level_block_index, inner_index));
+            unsafe{
+                let block_ptr = this.get_block_mut(Levels::LevelCount::default().dec(), level_block_index);
+                block_ptr.insert_child(inner_index, i);
+            }
+            
+            prev_index = Some(index);
+        }
+        this
+    }
+}
+
+impl<Levels, Data, R> FromIterator<(usize, Data)> for SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+    Self: Default
+{
+    /// Same as consecutive [insert]s. If your data is sorted -
+    /// use faster [from_sorted_iter].
+    /// 
+    /// [insert]: Self::insert
+    /// [from_sorted_iter]: Self::from_sorted_iter
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = (usize, Data)>>(iter: I) -> Self {
+        let mut this = Self::default();
+        this.extend(iter);
+        this
+    }
+}
+
+impl<Levels, Data, R> Extend<(usize, Data)> for SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = (usize, Data)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let additional = iter.size_hint().0;
+        self.reserve(additional);
+        for (index, value) in iter {
+            self.insert(index, value);
+        }
+    }
+}
+
 impl<Levels, Data> SparseTree<Levels, Data, ReqDefault>
 where
     Levels: SparseTreeLevels,
@@ -575,6 +871,80 @@ where
     }
 }
 
+impl<Levels, Data, R> Clone for SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels + Clone,
+    R: DefaultRequirement,
+    Data: Clone
+{
+    /// Copies levels as is, without re-inserting keys.
+    #[inline]
+    fn clone(&self) -> Self {
+        // Keep non-existent placeholder element uninitialized, if necessary.
+        let skip_first = !R::REQUIRED as usize;
+        let allocator = self.allocator().clone();
+        let values = if R::REQUIRED {
+            AllocVec::with_capacity_in(self.values.len(), allocator)
+        } else {
+            unsafe{ AllocVec::with_placeholder_in(self.values.len(), allocator) }
+        };
+        
+        let mut out = Self{
+            levels: self.levels.clone(),
+            values,
+            keys: self.keys.clone(),
+            last_level_block_indices: self.last_level_block_indices.clone(),
+            phantom_data: PhantomData
+        };
+        // Fill values last - if Data::clone() panics, `out` will drop
+        // only what is already there.
+        out.values.extend_from_slice(unsafe{ self.values.get_unchecked(skip_first..) });
+        out
+    }
+}
+
+impl<Levels, Data, R> fmt::Debug for SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+    Data: fmt::Debug
+{
+    /// Key-values in key order.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl_hibit_tree_cmp!(
+    [Levels, Data, R] SparseTree<Levels, Data, R>,
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement
+);
+
+impl<Levels, Data, R> Eq for SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+    Data: Eq
+{}
+
+impl<Levels, Data, R> Hash for SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+    Data: Hash
+{
+    /// Hashes key-values in key order.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.keys.len() - 1);
+        for (key, value) in self.iter() {
+            key.hash(state);
+            value.hash(state);
+        }
+    }
+}
+
 impl<Levels, Data, R> Borrowable for SparseTree<Levels, Data, R>
 where
     Levels: SparseTreeLevels,
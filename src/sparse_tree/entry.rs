@@ -0,0 +1,243 @@
+use crate::const_utils::{const_loop, ConstCopyArrayType, ConstInteger, ConstUsize};
+use crate::req_default::DefaultRequirement;
+use crate::sparse_tree_levels::SparseTreeLevels;
+use crate::Index;
+
+use super::SparseTree;
+
+/// A view into a single element of [SparseTree], which may either be vacant or occupied.
+///
+/// Constructed by [SparseTree::entry].
+pub enum Entry<'a, Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement
+{
+    Occupied(OccupiedEntry<'a, Levels, Data, R>),
+    Vacant(VacantEntry<'a, Levels, Data, R>),
+}
+
+/// Existing element of [SparseTree]. Part of [Entry].
+pub struct OccupiedEntry<'a, Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement
+{
+    tree: &'a mut SparseTree<Levels, Data, R>,
+    index: usize,
+    level_indices: ConstCopyArrayType<usize, Levels::LevelCount>,
+    levels_block_indices: ConstCopyArrayType<usize, Levels::LevelCount>,
+    data_block_index: usize,
+}
+
+/// Non-existent element of [SparseTree]. Part of [Entry].
+pub struct VacantEntry<'a, Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement
+{
+    tree: &'a mut SparseTree<Levels, Data, R>,
+    index: usize,
+    level_indices: ConstCopyArrayType<usize, Levels::LevelCount>,
+
+    /// Level of the deepest existing block at `index` path.
+    level: usize,
+    /// Deepest existing block index in its level.
+    level_block_index: usize,
+}
+
+impl<Levels, Data, R> SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+{
+    /// Element at `index` for in-place manipulation.
+    ///
+    /// Tree traversed only once, whatever you do with [Entry] next.
+    #[inline]
+    pub fn entry(&mut self, index: impl Into<Index<Levels::Mask, Levels::LevelCount>>)
+        -> Entry<'_, Levels, Data, R>
+    {
+        self.entry_impl(index.into().into())
+    }
+
+    #[inline]
+    pub(super) fn entry_impl(&mut self, index: usize) -> Entry<'_, Levels, Data, R> {
+        let level_indices = crate::level_indices::<Levels::Mask, Levels::LevelCount>(index);
+        let (levels_block_indices, data_block_index) = unsafe {
+            self.fetch_block_indices(level_indices.as_ref())
+        };
+
+        if data_block_index != 0 {
+            return Entry::Occupied(OccupiedEntry{
+                tree: self, index, level_indices, levels_block_indices, data_block_index
+            });
+        }
+
+        // Non-existent children point to block 0.
+        let level = unsafe{
+            levels_block_indices.as_ref().iter()
+                .position(|&block_index| block_index == 0)
+                .unwrap_unchecked()
+        };
+        let level_block_index = if level == 0 {
+            0
+        } else {
+            levels_block_indices.as_ref()[level - 1]
+        };
+        Entry::Vacant(VacantEntry{
+            tree: self, index, level_indices, level, level_block_index
+        })
+    }
+}
+
+impl<'a, Levels, Data, R> Entry<'a, Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement
+{
+    #[inline]
+    pub fn key(&self) -> usize {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    #[inline]
+    pub fn or_insert(self, value: Data) -> &'a mut Data {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(value),
+        }
+    }
+
+    #[inline]
+    pub fn or_insert_with(self, f: impl FnOnce() -> Data) -> &'a mut Data {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    #[inline]
+    pub fn or_insert_with_key(self, f: impl FnOnce(usize) -> Data) -> &'a mut Data {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = f(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn or_default(self) -> &'a mut Data
+    where
+        Data: Default
+    {
+        self.or_insert_with(Data::default)
+    }
+
+    /// Modify existing element, if any.
+    #[inline]
+    pub fn and_modify(mut self, f: impl FnOnce(&mut Data)) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, Levels, Data, R> OccupiedEntry<'a, Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement
+{
+    #[inline]
+    pub fn key(&self) -> usize {
+        self.index
+    }
+
+    #[inline]
+    pub fn get(&self) -> &Data {
+        unsafe{ self.tree.values.get_unchecked(self.data_block_index) }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut Data {
+        unsafe{ self.tree.values.get_unchecked_mut(self.data_block_index) }
+    }
+
+    #[inline]
+    pub fn into_mut(self) -> &'a mut Data {
+        unsafe{ self.tree.values.get_unchecked_mut(self.data_block_index) }
+    }
+
+    /// Replace value, returning the old one.
+    #[inline]
+    pub fn insert(&mut self, value: Data) -> Data {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Remove element from tree.
+    #[inline]
+    pub fn remove(self) -> Data {
+        unsafe{
+            self.tree.remove_existing(
+                self.level_indices, self.levels_block_indices, self.data_block_index
+            )
+        }
+    }
+}
+
+impl<'a, Levels, Data, R> VacantEntry<'a, Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement
+{
+    #[inline]
+    pub fn key(&self) -> usize {
+        self.index
+    }
+
+    /// Insert value. Missing blocks are created only now.
+    #[inline]
+    pub fn insert(self, value: Data) -> &'a mut Data {
+        let tree = self.tree;
+        let level_indices = self.level_indices;
+
+        let mut level_block_index = self.level_block_index;
+        const_loop!(LEVEL_INDEX in 0..{<Levels::LevelCount as ConstInteger>::Dec::VALUE} => {
+            let level_index = ConstUsize::<LEVEL_INDEX>;
+            if level_index.value() >= self.level {
+                // 1. Insert new block in next level
+                let new_level_block_index = tree.insert_empty_block(level_index.inc());
+
+                // 2. Insert new block index as a child
+                unsafe{
+                    let block_ptr = tree.get_block_mut(level_index, level_block_index);
+                    block_ptr.insert_child(level_indices.as_ref()[LEVEL_INDEX], new_level_block_index);
+                }
+
+                level_block_index = new_level_block_index;
+            }
+        });
+
+        // 3. Last level
+        let last_level_inner_index = unsafe{ *level_indices.as_ref().last().unwrap_unchecked() };
+        let i = tree.values.len();
+        tree.values.push(value);
+        tree.keys.push(self.index);
+        tree.last_level_block_indices.push(
+            (level_block_index, last_level_inner_index)
+        );
+        unsafe{
+            let block_ptr = tree.get_block_mut(Levels::LevelCount::default().dec(), level_block_index);
+            block_ptr.insert_child(last_level_inner_index, i);
+
+            // 4. Data
+            tree.values.get_unchecked_mut(i)
+        }
+    }
+}
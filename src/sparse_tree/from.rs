@@ -0,0 +1,176 @@
+use std::ptr;
+
+use crate::const_utils::{ConstInteger, ConstUsize};
+use crate::req_default::DefaultRequirement;
+use crate::sparse_tree_levels::SparseTreeLevels;
+use crate::dense::DataIndex;
+use crate::{
+    map, BitBlock, DenseTree, FromHibitTree, HibitTree, HibitTreeCursor,
+    HibitTreeCursorTypes, HibitTreeTypes, LazyHibitTree, NodeAllocator
+};
+
+use super::retain::RemoveEmptyBlock;
+use super::SparseTree;
+
+impl<Levels, Data, R> SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+{
+    /// Fill block at level `n`, for `other` node with `level_index`.
+    ///
+    /// Children filled first, then connected to the block.
+    /// Returns block index, or `None` if `other` node turned out to be empty -
+    /// possible only with non-[EXACT_HIERARCHY].
+    ///
+    /// [EXACT_HIERARCHY]: HibitTree::EXACT_HIERARCHY
+    unsafe fn fill_block<'src, From, N>(
+        &mut self,
+        other: &'src From,
+        other_cursor: &mut <From as HibitTreeTypes<'src>>::Cursor,
+        n: N,
+        level_index: usize,
+        key_acc: usize,
+    ) -> Option<usize>
+    where
+        From: HibitTree<LevelMask = Levels::Mask, LevelCount = Levels::LevelCount>,
+        for<'a> From: HibitTreeTypes<'a,
+            Cursor: for<'b> HibitTreeCursorTypes<'b, Data = Data>,
+        >,
+        N: ConstInteger,
+    {
+        let mask = other_cursor.select_level_node_unchecked(other, n, level_index);
+        if mask.is_zero() {
+            return None;
+        }
+
+        // Root block always exists.
+        let block_index = if N::VALUE == 0 { 0 } else { self.insert_empty_block(n) };
+
+        if N::VALUE == Levels::LevelCount::VALUE - 1 {
+            // terminal block with data
+            for index in mask.into_bits_iter() {
+                let data = other_cursor.data_unchecked(other, index);
+                let data_index = self.values.len();
+                self.values.push(data);
+                self.keys.push(key_acc + index);
+                self.last_level_block_indices.push((block_index, index));
+                self.get_block_mut(n, block_index).insert_child(index, data_index);
+            }
+            return Some(block_index);
+        }
+
+        let level_exp = Levels::Mask::SIZE.ilog2() as usize * (Levels::LevelCount::VALUE - N::VALUE - 1);
+        for index in mask.into_bits_iter() {
+            let child = self.fill_block(other, other_cursor, n.inc(), index, key_acc + (index << level_exp));
+            if let Some(child_block_index) = child {
+                self.get_block_mut(n, block_index).insert_child(index, child_block_index);
+            }
+        }
+
+        if !From::EXACT_HIERARCHY && N::VALUE != 0 && self.get_block_mut(n, block_index).get_mask().is_zero() {
+            self.levels.visit_mut(n, RemoveEmptyBlock(block_index));
+            return None;
+        }
+        Some(block_index)
+    }
+}
+
+impl<From, Levels, Data, R> FromHibitTree<From> for SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+    Self: Default,
+    From: HibitTree<
+        LevelMask  = Levels::Mask,
+        LevelCount = Levels::LevelCount,
+    >,
+    for<'a> From: HibitTreeTypes<'a,
+        Cursor: for<'b> HibitTreeCursorTypes<'b,
+            Data = Data
+        >,
+    >,
+{
+    fn from_sparse_hierarchy(other: From) -> Self {
+        let mut this = Self::default();
+        let mut other_cursor = <From as HibitTreeTypes>::Cursor::new(&other);
+        unsafe{
+            this.fill_block(&other, &mut other_cursor, ConstUsize::<0>, 0, 0);
+        }
+        this
+    }
+}
+
+trait ForgetValues {
+    /// See [SparseTree::forget_values].
+    unsafe fn forget_values(&mut self);
+}
+
+impl<T, const DEPTH: usize, I: DataIndex, A: NodeAllocator> ForgetValues for DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger
+{
+    #[inline]
+    unsafe fn forget_values(&mut self) {
+        self.forget_values()
+    }
+}
+
+impl<Levels, T, R> ForgetValues for SparseTree<Levels, T, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+{
+    #[inline]
+    unsafe fn forget_values(&mut self) {
+        self.forget_values()
+    }
+}
+
+/// Tree, which values are moved out.
+///
+/// Disowns values before dropping tree, even if moving panics - values
+/// already moved are owned by the new tree, the rest are leaked.
+struct ForgetValuesOnDrop<Tree: ForgetValues>(Tree);
+
+impl<Tree: ForgetValues> Drop for ForgetValuesOnDrop<Tree> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe{ self.0.forget_values(); }
+    }
+}
+
+impl<Levels, T, R, const DEPTH: usize, I: DataIndex, A: NodeAllocator> From<DenseTree<T, DEPTH, I, A>> for SparseTree<Levels, T, R>
+where
+    ConstUsize<DEPTH>: ConstInteger,
+    Levels: SparseTreeLevels<Mask = u64, LevelCount = ConstUsize<DEPTH>>,
+    R: DefaultRequirement,
+    Self: Default,
+{
+    /// Moves values out of [DenseTree], in key order.
+    /// 
+    /// Hierarchy built from [DenseTree] node masks directly, without per-key insertion.
+    #[inline]
+    fn from(tree: DenseTree<T, DEPTH, I, A>) -> Self {
+        let tree = ForgetValuesOnDrop(tree);
+        // Exact hierarchy - each value read exactly once.
+        map(&tree.0, |value: &T| unsafe{ ptr::read(value) }).materialize()
+    }
+}
+
+impl<Levels, T, R, const DEPTH: usize, I: DataIndex, A: NodeAllocator + Clone + Default> From<SparseTree<Levels, T, R>> for DenseTree<T, DEPTH, I, A>
+where
+    ConstUsize<DEPTH>: ConstInteger,
+    Levels: SparseTreeLevels<Mask = u64, LevelCount = ConstUsize<DEPTH>>,
+    R: DefaultRequirement,
+{
+    /// Moves values out of [SparseTree], in key order.
+    /// 
+    /// Hierarchy built from [SparseTree] block masks directly, without per-key insertion.
+    #[inline]
+    fn from(tree: SparseTree<Levels, T, R>) -> Self {
+        let tree = ForgetValuesOnDrop(tree);
+        // Exact hierarchy - each value read exactly once.
+        map(&tree.0, |value: &T| unsafe{ ptr::read(value) }).materialize()
+    }
+}
@@ -0,0 +1,81 @@
+use std::mem::size_of;
+use std::ops::ControlFlow;
+use std::ops::ControlFlow::Continue;
+
+use crate::bit_block::BitBlock;
+use crate::const_utils::{ConstInteger, ConstUsize};
+use crate::level::ILevel;
+use crate::level_block::HiBlock;
+use crate::req_default::DefaultRequirement;
+use crate::sparse_tree_levels::{FoldVisitor, SparseTreeLevels};
+use crate::{LevelMemoryStats, MemoryStats};
+
+use super::SparseTree;
+
+/// Fills block sizes related stats, for already counted nodes.
+struct LevelSizes<'a>(&'a mut MemoryStats);
+
+impl<'a, M: BitBlock> FoldVisitor<M> for LevelSizes<'a> {
+    type Acc = ();
+
+    #[inline]
+    fn visit<I: ConstInteger, L>(&mut self, _: I, level: &L, _: ()) -> ControlFlow<(), ()>
+    where
+        L: ILevel,
+        L::Block: HiBlock<Mask=M>
+    {
+        let block_size = size_of::<L::Block>();
+        let stats = &mut self.0.levels[I::VALUE];
+        stats.allocated_slots = stats.nodes * M::SIZE;
+        stats.mask_bytes = stats.nodes * size_of::<M>();
+        stats.bytes = stats.nodes * block_size;
+        
+        // Non-root level's first block is an empty block sentinel.
+        let sentinels = (I::VALUE != 0) as usize;
+        let free_blocks = level.blocks_capacity() - stats.nodes - sentinels;
+        self.0.sentinel_bytes += sentinels * block_size;
+        self.0.free_bytes += free_blocks * block_size;
+        Continue(())
+    }
+}
+
+impl<Levels, Data, R> SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+{
+    /// Memory usage. Traverses all blocks.
+    #[inline]
+    pub fn memory_stats(&self) -> MemoryStats {
+        let mut stats = MemoryStats{
+            levels: vec![LevelMemoryStats::default(); Levels::LevelCount::VALUE],
+            data_bytes: self.values.capacity() * size_of::<Data>(),
+            keys_bytes: self.keys.capacity() * size_of::<usize>()
+                + self.last_level_block_indices.capacity() * size_of::<(usize, usize)>(),
+            ..Default::default()
+        };
+        unsafe{ self.block_stats(ConstUsize::<0>, 0, &mut stats.levels); }
+        self.levels.fold((), LevelSizes(&mut stats));
+        stats
+    }
+
+    /// Counts blocks in use, and their children.
+    unsafe fn block_stats<N: ConstInteger>(
+        &self,
+        n: N,
+        block_index: usize,
+        levels: &mut [LevelMemoryStats]
+    ) {
+        let block = self.get_block(n, block_index);
+        let mask = block.get_mask();
+        let stats = &mut levels[N::VALUE];
+        stats.nodes += 1;
+        stats.used_slots += mask.count_ones();
+
+        if N::VALUE != Levels::LevelCount::VALUE - 1 {
+            for inner_index in mask.clone().into_bits_iter() {
+                self.block_stats(n.inc(), block.get_child(inner_index), levels);
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::{mem, panic, ptr, vec};
+use std::panic::AssertUnwindSafe;
+
+use crate::bit_block::BitBlock;
+use crate::const_utils::{ConstInteger, ConstUsize};
+use crate::level::ILevel;
+use crate::level_block::HiBlock;
+use crate::prune::{KeepAll, Prune, RemoveAll};
+use crate::req_default::DefaultRequirement;
+use crate::sparse_tree_levels::{MutVisitor, SparseTreeLevels};
+use crate::alloc_vec::AllocVec;
+use crate::NodeAllocator;
+
+use super::SparseTree;
+
+struct Retain<Data, A: NodeAllocator, F, R> {
+    /// Elements are moved out of here, one by one.
+    old_values: *mut Data,
+    old_keys: AllocVec<usize, A>,
+
+    /// `true` - keep element.
+    f: F,
+    /// Receives removed element.
+    on_remove: R,
+}
+
+/// If retain's `f` panics - tree becomes empty.
+///
+/// Not yet visited elements leaked.
+struct ClearOnPanic<'a, Levels, Data, R>(&'a mut SparseTree<Levels, Data, R>)
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement;
+
+impl<'a, Levels, Data, R> Drop for ClearOnPanic<'a, Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement
+{
+    #[inline]
+    fn drop(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl<Levels, Data, R> SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+{
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// Elements visited in key order. Single pass over the tree.
+    /// Values rebuilt in key order, and empty blocks are removed.
+    ///
+    /// If `f` panics, tree is left empty, and not yet visited elements are leaked.
+    #[inline]
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize, &mut Data) -> bool
+    {
+        self.retain_impl(|key, value: &mut Data| f(key, value), |_, value| drop(value), &mut KeepAll);
+    }
+
+    /// Removes all elements matching `pred`, and returns them in key order.
+    ///
+    /// Eager - all matching elements are removed immediately,
+    /// within the same single pass as [retain].
+    ///
+    /// If `pred` panics, the pass still completes, and then panic resumes.
+    /// Elements extracted before the panic are dropped. The rest, including
+    /// the one `pred` panicked on, stay in the tree.
+    ///
+    /// [retain]: Self::retain
+    #[inline]
+    pub fn extract_if<F>(&mut self, mut pred: F) -> vec::IntoIter<(usize, Data)>
+    where
+        F: FnMut(usize, &mut Data) -> bool
+    {
+        let mut out = Vec::new();
+        let mut panic = None;
+        self.retain_impl(
+            |key, value: &mut Data| {
+                // After `pred` panicked - keep the rest, to finish the pass.
+                if panic.is_some() {
+                    return true;
+                }
+                match panic::catch_unwind(AssertUnwindSafe(|| pred(key, value))) {
+                    Ok(extract) => !extract,
+                    Err(payload) => {
+                        panic = Some(payload);
+                        true
+                    }
+                }
+            },
+            |key, value| out.push((key, value)),
+            &mut KeepAll
+        );
+        if let Some(payload) = panic {
+            panic::resume_unwind(payload);
+        }
+        out.into_iter()
+    }
+
+    /// `prune` removes whole subtrees, before `f` get called.
+    pub(super) fn retain_impl<F, OnRemove, P>(&mut self, f: F, on_remove: OnRemove, prune: &mut P)
+    where
+        F: FnMut(usize, &mut Data) -> bool,
+        OnRemove: FnMut(usize, Data),
+        P: Prune<Levels::Mask>,
+    {
+        let len = self.len();
+        let allocator = self.allocator().clone();
+        let mut old_values = mem::replace(&mut self.values, unsafe{ AllocVec::with_placeholder_in(len + 1, allocator.clone()) });
+        let old_keys = mem::replace(&mut self.keys, {
+            let mut keys = AllocVec::with_capacity_in(len + 1, allocator);
+            keys.push(usize::MAX);
+            keys
+        });
+        self.last_level_block_indices.clear();
+        self.last_level_block_indices.push((0, 0));
+        unsafe{
+            // Move placeholder/default. Bitwise, since it may be uninitialized.
+            ptr::copy_nonoverlapping(old_values.as_ptr(), self.values.as_mut_ptr(), 1);
+            // Values now owned by `Retain`. Dropping `old_values` will only deallocate.
+            old_values.set_len(0);
+        }
+
+        let mut ctx = Retain{
+            old_values: old_values.as_mut_ptr(),
+            old_keys,
+            f,
+            on_remove
+        };
+
+        let guard = ClearOnPanic(self);
+        unsafe{
+            guard.0.retain_block(ConstUsize::<0>, 0, 0, &mut ctx, prune);
+        }
+        mem::forget(guard);
+    }
+
+    /// Returns true if block become empty.
+    ///
+    /// `level_index` - block index in parent.
+    unsafe fn retain_block<N, F, OnRemove, P>(
+        &mut self,
+        n: N,
+        block_index: usize,
+        level_index: usize,
+        ctx: &mut Retain<Data, Levels::Allocator, F, OnRemove>,
+        prune: &mut P,
+    ) -> bool
+    where
+        N: ConstInteger,
+        F: FnMut(usize, &mut Data) -> bool,
+        OnRemove: FnMut(usize, Data),
+        P: Prune<Levels::Mask>,
+    {
+        let block = self.get_block_mut(n, block_index);
+        let mask = block.get_mask().clone();
+        let (keep, descend) = prune.enter(n, level_index, mask.clone());
+
+        if N::VALUE == Levels::LevelCount::VALUE - 1 {
+            for inner_index in mask.into_bits_iter() {
+                let old_index = block.get_child(inner_index);
+                let key = *ctx.old_keys.get_unchecked(old_index);
+                let value = &mut *ctx.old_values.add(old_index);
+                if keep.get_bit(inner_index) && (ctx.f)(key, value) {
+                    let data_index = self.values.len();
+                    self.values.push(ptr::read(value));
+                    self.keys.push(key);
+                    self.last_level_block_indices.push((block_index, inner_index));
+                    block.set_child(inner_index, data_index);
+                } else {
+                    block.remove_child(inner_index);
+                    (ctx.on_remove)(key, ptr::read(value));
+                }
+            }
+        } else {
+            for inner_index in mask.into_bits_iter() {
+                let child_index = block.get_child(inner_index);
+                let empty = if !keep.get_bit(inner_index) {
+                    self.retain_block(n.inc(), child_index, inner_index, ctx, &mut RemoveAll)
+                } else if descend.get_bit(inner_index) {
+                    self.retain_block(n.inc(), child_index, inner_index, ctx, prune)
+                } else {
+                    self.retain_block(n.inc(), child_index, inner_index, ctx, &mut KeepAll)
+                };
+                if empty {
+                    self.levels.visit_mut(n.inc(), RemoveEmptyBlock(child_index));
+                    block.remove_child(inner_index);
+                }
+            }
+        }
+
+        block.get_mask().is_zero()
+    }
+}
+
+pub(super) struct RemoveEmptyBlock(pub usize);
+impl<M> MutVisitor<M> for RemoveEmptyBlock {
+    type Out = ();
+
+    #[inline(always)]
+    fn visit<I: ConstInteger, L>(self, _: I, level: &mut L) -> Self::Out
+    where
+        L: ILevel<Block: HiBlock>,
+    {
+        unsafe{ level.remove_empty_block_unchecked(self.0); }
+    }
+}
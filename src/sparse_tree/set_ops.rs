@@ -0,0 +1,94 @@
+use std::{mem, ptr};
+
+use crate::prune::{Intersect, Subtract};
+use crate::req_default::DefaultRequirement;
+use crate::sparse_tree_levels::SparseTreeLevels;
+use crate::{HibitTree, HibitTreeData, RegularHibitTree};
+
+use super::entry::{Entry, OccupiedEntry};
+use super::SparseTree;
+
+/// Removes element, which value was moved out, if merge panics.
+struct RemoveOnPanic<'a, Levels, Data, R>(Option<OccupiedEntry<'a, Levels, Data, R>>)
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement;
+
+impl<'a, Levels, Data, R> Drop for RemoveOnPanic<'a, Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement
+{
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(entry) = self.0.take() {
+            // Value already moved out.
+            mem::forget(entry.remove());
+        }
+    }
+}
+
+impl<Levels, Data, R> SparseTree<Levels, Data, R>
+where
+    Levels: SparseTreeLevels,
+    R: DefaultRequirement,
+{
+    /// Keep only elements with indices in `other`.
+    ///
+    /// Blocks not in `other` removed as a whole, by block masks.
+    /// Same as [retain], values rebuilt in key order.
+    ///
+    /// [retain]: Self::retain
+    #[inline]
+    pub fn intersect_with<O>(&mut self, other: &O)
+    where
+        O: HibitTree<LevelMask = Levels::Mask, LevelCount = Levels::LevelCount>
+    {
+        self.retain_impl(|_, _: &mut Data| true, |_, value| drop(value), &mut Intersect::new(other));
+    }
+
+    /// Remove elements with indices in `other`.
+    ///
+    /// Only blocks, that are in `other`, visited element by element.
+    /// Same as [retain], values rebuilt in key order.
+    ///
+    /// [retain]: Self::retain
+    #[inline]
+    pub fn difference_with<O>(&mut self, other: &O)
+    where
+        O: HibitTree<LevelMask = Levels::Mask, LevelCount = Levels::LevelCount>
+    {
+        self.retain_impl(|_, _: &mut Data| true, |_, value| drop(value), &mut Subtract::new(other));
+    }
+
+    /// Merge all `other` elements into `self`.
+    ///
+    /// `f(Option<Data>, other_data) -> Data` receives existing element if any,
+    /// and returns a new one.
+    ///
+    /// Existing element is merged in place, with a single tree lookup per
+    /// `other` element. If `f` panics while merging, that element is removed.
+    ///
+    /// See [crate::union_with] for lazy union.
+    #[inline]
+    pub fn union_with<'a, O, F>(&mut self, other: &'a O, mut f: F)
+    where
+        O: RegularHibitTree<LevelMask = Levels::Mask, LevelCount = Levels::LevelCount>,
+        F: FnMut(Option<Data>, HibitTreeData<'a, O>) -> Data
+    {
+        for (index, data) in other.iter() {
+            match self.entry(index) {
+                Entry::Occupied(e) => {
+                    let mut guard = RemoveOnPanic(Some(e));
+                    unsafe{
+                        let slot: *mut Data = guard.0.as_mut().unwrap_unchecked().get_mut();
+                        let value = ptr::read(slot);
+                        ptr::write(slot, f(Some(value), data));
+                    }
+                    guard.0 = None;
+                }
+                Entry::Vacant(e) => { e.insert(f(None, data)); }
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
 use std::ops::ControlFlow;
-use crate::BitBlock;
+use crate::{BitBlock, NodeAllocator};
 use crate::const_utils::{const_for_rev, ConstIntVisitor};
 use crate::const_utils::const_int::{ConstUsize, ConstInteger};
 use crate::level::ILevel;
@@ -50,10 +50,16 @@ pub trait FoldMutVisitor<Mask> {
 }
 
 
-pub trait SparseTreeLevels: Default {
+pub trait SparseTreeLevels {
     type LevelCount: ConstInteger;
     type Mask: BitBlock;
     
+    /// Memory source of all levels.
+    type Allocator: NodeAllocator + Clone;
+    
+    /// Each level gets a clone of `allocator`.
+    fn new_in(allocator: Self::Allocator) -> Self;
+    
     fn visit_type<I: ConstInteger, V: TypeVisitor<Self::Mask>>(i: I, visitor: V) -> V::Out;
     
     fn visit<I: ConstInteger, V: Visitor<Self::Mask>>(&self, i: I, visitor: V) -> V::Out;
@@ -78,15 +84,24 @@ macro_rules! sparse_array_levels_impl {
     ($n:literal: [$($i:tt,)+] [$($rev_i:tt,)+]; $first_t:tt, $($t:tt,)* ) => {
         impl<$first_t, $($t,)*> SparseTreeLevels for ($first_t, $($t,)*)
         where
-            $first_t: ILevel,
+            $first_t: ILevel<Allocator: Clone>,
             $first_t::Block: HiBlock,
             $(
-                $t: ILevel,
+                $t: ILevel<Allocator = $first_t::Allocator>,
                 $t::Block: HiBlock<Mask = <$first_t::Block as HiBlock>::Mask>,
             )*
         {
             type LevelCount = ConstUsize<$n>;       
             type Mask = <$first_t::Block as HiBlock>::Mask;
+            type Allocator = $first_t::Allocator;
+            
+            #[inline]
+            fn new_in(allocator: Self::Allocator) -> Self {
+                (
+                    $first_t::new_in(allocator.clone()),
+                    $($t::new_in(allocator.clone()),)*
+                )
+            }
             
             #[inline(always)]
             fn visit_type<I: ConstInteger, V: TypeVisitor<Self::Mask>>(i: I, mut visitor: V) -> V::Out {
@@ -0,0 +1,38 @@
+/// One of two values.
+///
+/// Used as [SymmetricDifference] data.
+///
+/// [SymmetricDifference]: crate::ops::SymmetricDifference
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Either<L, R>{
+    Left(L),
+    Right(R),
+}
+
+impl<L, R> Either<L, R>{
+    #[inline]
+    pub fn left(self) -> Option<L> {
+        match self {
+            Either::Left(l) => Some(l),
+            Either::Right(_) => None,
+        }
+    }
+
+    #[inline]
+    pub fn right(self) -> Option<R> {
+        match self {
+            Either::Left(_) => None,
+            Either::Right(r) => Some(r),
+        }
+    }
+
+    #[inline]
+    pub fn is_left(&self) -> bool {
+        matches!(self, Either::Left(_))
+    }
+
+    #[inline]
+    pub fn is_right(&self) -> bool {
+        matches!(self, Either::Right(_))
+    }
+}
@@ -3,4 +3,45 @@ pub trait LendingIterator{
     type Item<'a> where Self:'a;
     
     fn next(&mut self) -> Option<Self::Item<'_>>;
-}
\ No newline at end of file
+}
+
+/// [LendingIterator] that can be iterated from the back.
+pub trait DoubleEndedLendingIterator: LendingIterator {
+    fn next_back(&mut self) -> Option<Self::Item<'_>>;
+    
+    /// Reverse iteration direction.
+    #[inline]
+    fn rev(self) -> Rev<Self>
+    where
+        Self: Sized
+    {
+        Rev(self)
+    }
+}
+
+/// Reversed [DoubleEndedLendingIterator].
+/// 
+/// Constructed by [DoubleEndedLendingIterator::rev].
+pub struct Rev<I>(I);
+
+impl<I> LendingIterator for Rev<I>
+where
+    I: DoubleEndedLendingIterator
+{
+    type Item<'a> = I::Item<'a> where Self: 'a;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        self.0.next_back()
+    }
+}
+
+impl<I> DoubleEndedLendingIterator for Rev<I>
+where
+    I: DoubleEndedLendingIterator
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item<'_>> {
+        self.0.next()
+    }
+}
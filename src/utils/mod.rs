@@ -4,6 +4,7 @@ mod take;
 mod borrowable;
 mod function;
 mod lending_iterator;
+mod either;
 
 pub use primitive::*;
 pub use array::*;
@@ -11,6 +12,7 @@ pub use take::*;
 pub use borrowable::*;
 pub use function::*;
 pub use lending_iterator::*;
+pub use either::*;
 
 /// Trait for &.
 pub trait Ref {
@@ -35,6 +35,7 @@ pub trait Primitive:
     fn as_u8(self) -> u8;
     
     fn trailing_zeros(self) -> u32;
+    fn leading_zeros(self) -> u32;
     fn wrapping_neg(self) -> Self;
     
     fn is_zero(self) -> bool;
@@ -69,6 +70,11 @@ macro_rules! impl_primitive {
                 self.trailing_zeros()
             }
 
+            #[inline]
+            fn leading_zeros(self) -> u32 {
+                self.leading_zeros()
+            }
+
             #[inline]
             fn wrapping_neg(self) -> Self {
                 self.wrapping_neg()
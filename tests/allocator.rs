@@ -0,0 +1,121 @@
+use std::alloc::Layout;
+use std::cell::Cell;
+use std::ptr::{self, NonNull};
+use itertools::assert_equal;
+use hibit_tree::{config, DenseTree, Global, HibitTree, NodeAllocator, SparseTree};
+
+/// Counts live allocations.
+#[derive(Default)]
+struct Counter {
+    live: Cell<isize>,
+    total: Cell<usize>,
+}
+
+unsafe impl NodeAllocator for Counter {
+    fn allocate(&self, layout: Layout) -> NonNull<u8> {
+        self.live.set(self.live.get() + 1);
+        self.total.set(self.total.get() + 1);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.live.set(self.live.get() - 1);
+        Global.deallocate(ptr, layout)
+    }
+}
+
+thread_local! {
+    static COUNTER: Counter = Counter::default();
+}
+
+/// Stateless handle to [COUNTER].
+#[derive(Default, Clone)]
+struct ThreadCounter;
+
+unsafe impl NodeAllocator for ThreadCounter {
+    fn allocate(&self, layout: Layout) -> NonNull<u8> {
+        COUNTER.with(|c| c.allocate(layout))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        COUNTER.with(|c| c.deallocate(ptr, layout))
+    }
+}
+
+#[test]
+fn dense_tree_allocator_test(){
+    let counter = Counter::default();
+    {
+        let mut a: DenseTree<usize, 3, u32, &Counter> = DenseTree::new_in(&counter);
+        for k in 0..1000 {
+            a.insert(k * 37, k);
+        }
+        assert!(counter.live.get() > 1);
+        for k in 0..500 {
+            assert_eq!(a.remove(k * 37), Some(k));
+        }
+        assert_equal(Iterator::map(a.iter(), |(k, v)| (k, *v)), (500..1000).map(|k| (k * 37, k)));
+        
+        let b = a.clone();
+        assert_eq!(a, b);
+        
+        a.shrink_to_fit();
+        a.clear();
+    }
+    assert!(counter.total.get() > 0);
+    assert_eq!(counter.live.get(), 0);
+}
+
+#[test]
+fn sparse_tree_allocator_test(){
+    type Tree<'a> = SparseTree<config::width_64::depth_3<&'a Counter>, usize>;
+    let counter = Counter::default();
+    {
+        let mut a = Tree::new_in(&counter);
+        a.extend((0..1000).map(|k| (k * 37, k)));
+        assert!(counter.live.get() > 0);
+        for k in 0..500 {
+            assert_eq!(a.remove(k * 37), Some(k));
+        }
+        let b = a.clone();
+        assert!(ptr::eq(*b.allocator(), &counter));
+        assert_equal(Iterator::map(b.iter(), |(k, v)| (k, *v)), (500..1000).map(|k| (k * 37, k)));
+        
+        a.clear();
+        assert!(a.is_empty());
+    }
+    assert!(counter.total.get() > 0);
+    assert_eq!(counter.live.get(), 0);
+}
+
+#[test]
+fn elements_allocator_test(){
+    let counter = Counter::default();
+    {
+        // Single inline block - only elements storage allocates.
+        let mut s: SparseTree<config::width_64::depth_1<&Counter>, usize> = SparseTree::new_in(&counter);
+        let total = counter.total.get();
+        s.reserve(100);
+        assert!(counter.total.get() > total);
+        
+        let mut d: DenseTree<usize, 3, u32, &Counter> = DenseTree::new_in(&counter);
+        let total = counter.total.get();
+        d.reserve(100);
+        assert!(counter.total.get() > total);
+    }
+    assert_eq!(counter.live.get(), 0);
+}
+
+#[test]
+fn dense_tree_from_sorted_iter_panic_test(){
+    use std::panic::catch_unwind;
+    type Tree = DenseTree<usize, 3, u32, ThreadCounter>;
+    // Unsorted tail, after many nodes were built.
+    let keys = (0..1000).map(|k| k * 37).chain([5]);
+    let r = catch_unwind(|| Tree::from_sorted_iter(keys.map(|k| (k, k))));
+    assert!(r.is_err());
+    COUNTER.with(|c| {
+        assert!(c.total.get() > 0);
+        assert_eq!(c.live.get(), 0);
+    });
+}
@@ -80,4 +80,151 @@ fn materialize_test(){
         .map(|(l, _r) : (Option<&Data>, Option<&Data>)| l.unwrap().clone())
         .materialize();
     assert_equal(ao.iter(), a1.iter());
-}
\ No newline at end of file
+}
+
+#[test]
+fn materialize_empty_intersection_test(){
+    let mut a1 = Array::default();
+    let mut a2 = Array::default();
+    // Same terminal node, no common keys.
+    a1.insert(0, Data(0));
+    a2.insert(1, Data(1));
+    
+    let ao: Array = map(intersection(&a1, &a2), |(l, _r) : (&Data, &Data)| l.clone()).materialize();
+    assert_eq!(ao.iter().count(), 0);
+    assert_eq!(ao, Array::default());
+    
+    // Materialized tree must be usable.
+    let mut ao = ao;
+    ao.insert(5, Data(5));
+    assert_equal(ao.iter(), [(5, &Data(5))]);
+}
+
+#[test]
+fn materialize_sparse_test(){
+    use hibit_tree::{config, SparseTree};
+    type Tree = SparseTree<config::width_64::depth_4, Data>;
+    
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0x3dee3a0fe15bb9db);
+    let a1: Tree = (0..4000).map(|_| { let v = rng.gen_range(0..common::RANGE); (v, Data(v)) }).collect();
+    let a2: Tree = (0..4000).map(|_| { let v = rng.gen_range(0..common::RANGE); (v, Data(v)) }).collect();
+    
+    // Exact hierarchy.
+    let m: Tree = map(&a1, |d: &Data| d.clone()).materialize();
+    assert_equal(m.iter(), a1.iter());
+    
+    // Non-exact hierarchy.
+    let mut m: Tree = map(intersection(&a1, &a2), |(l, _r): (&Data, &Data)| l.clone()).materialize();
+    let expected: Vec<usize> = a1.iter().map(|(k, _)| k).filter(|&k| a2.contains(k)).collect();
+    assert_equal(m.iter().map(|(k, _)| k), expected.iter().copied());
+    assert_eq!(m.len(), expected.len());
+    
+    // remove() relies on last level block indices.
+    for &k in expected.iter().step_by(2) {
+        assert_eq!(m.remove(k), Some(Data(k)));
+    }
+    assert_equal(m.iter().map(|(k, _)| k), expected.iter().copied().skip(1).step_by(2));
+    m.insert(5, Data(5));
+    assert_eq!(m.get(5), Some(&Data(5)));
+    
+    // Empty intersection.
+    let b1: Tree = [(0, Data(0))].into_iter().collect();
+    let b2: Tree = [(1, Data(1))].into_iter().collect();
+    let mut m: Tree = map(intersection(&b1, &b2), |(l, _r): (&Data, &Data)| l.clone()).materialize();
+    assert!(m.is_empty());
+    assert_eq!(m, Tree::default());
+    m.insert(1, Data(1));
+    assert_equal(m.iter(), [(1, &Data(1))]);
+    
+    // From DenseTree.
+    let d: DenseTree<usize, 4> = [(3, 3), (70_000, 70_000)].into_iter().collect();
+    let s: SparseTree<config::width_64::depth_4, usize> = map(&d, |v: &usize| *v).materialize();
+    assert_equal(s.iter(), d.iter());
+    
+    // Default placeholder must survive.
+    type DefaultTree = SparseTree<config::width_64::depth_2, String, hibit_tree::ReqDefault>;
+    let d: DenseTree<usize, 2> = [(3, 3), (4000, 4000)].into_iter().collect();
+    let s: DefaultTree = map(&d, |v: &usize| v.to_string()).materialize();
+    assert_eq!(s.get_or_default(3), "3");
+    assert_eq!(s.get_or_default(5), "");
+}
+
+#[test]
+fn dense_sparse_conversion_test(){
+    use std::rc::Rc;
+    use hibit_tree::{config, SparseTree};
+    type Sparse = SparseTree<config::width_64::depth_3, Rc<usize>>;
+    type Dense  = DenseTree<Rc<usize>, 3>;
+    
+    let counter = Rc::new(0);
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0x5bb9db3dee3a0fe1);
+    let keys: Vec<usize> = (0..2000).map(|_| rng.gen_range(0..64*64*64)).collect();
+    let d: Dense = keys.iter().map(|&k| (k, counter.clone())).collect();
+    let expected: Vec<usize> = d.iter().map(|(k, _)| k).collect();
+    assert_eq!(Rc::strong_count(&counter), expected.len() + 1);
+    
+    // Values moved, not cloned.
+    let mut s: Sparse = d.into();
+    assert_eq!(Rc::strong_count(&counter), expected.len() + 1);
+    assert_equal(s.iter().map(|(k, _)| k), expected.iter().copied());
+    for &k in expected.iter().step_by(2) {
+        assert!(s.remove(k).is_some());
+    }
+    s.insert(7, counter.clone());
+    
+    let expected: Vec<usize> = s.iter().map(|(k, _)| k).collect();
+    let mut d: Dense = s.into();
+    assert_eq!(Rc::strong_count(&counter), expected.len() + 1);
+    assert_equal(d.iter().map(|(k, _)| k), expected.iter().copied());
+    for &k in expected.iter().step_by(2) {
+        assert!(d.remove(k).is_some());
+    }
+    d.insert(1, counter.clone());
+    let len = d.iter().count();
+    assert_eq!(Rc::strong_count(&counter), len + 1);
+    drop(d);
+    assert_eq!(Rc::strong_count(&counter), 1);
+    
+    // Default placeholder must survive.
+    type DefaultTree = SparseTree<config::width_64::depth_2, String, hibit_tree::ReqDefault>;
+    let d: DenseTree<String, 2> = [(3, "3".to_string())].into_iter().collect();
+    let s: DefaultTree = d.into();
+    assert_eq!(s.get_or_default(3), "3");
+    assert_eq!(s.get_or_default(5), "");
+    let d: DenseTree<String, 2> = s.into();
+    assert_equal(d.iter(), [(3, &"3".to_string())]);
+    
+    // Empty.
+    let s: Sparse = Dense::default().into();
+    assert!(s.is_empty());
+}
+
+#[test]
+fn dense_sparse_conversion_panic_test(){
+    use std::cell::RefCell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+    use hibit_tree::{config, SparseTree};
+    
+    struct DropCounter(usize, Rc<RefCell<Vec<usize>>>);
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.1.borrow_mut()[self.0] += 1;
+        }
+    }
+    
+    const COUNT: usize = 300;
+    let drops = Rc::new(RefCell::new(vec![0; COUNT]));
+    let s: SparseTree<config::width_64::depth_3, DropCounter> = 
+        (0..COUNT).map(|k| (k, DropCounter(k, drops.clone()))).collect();
+    
+    // u8 data index can't address all elements.
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _: DenseTree<DropCounter, 3, u8> = s.into();
+    }));
+    assert!(result.is_err());
+    // Already moved values dropped exactly once, the rest leaked.
+    let drops = drops.borrow();
+    assert!(drops.iter().all(|&n| n <= 1));
+    assert!(drops.iter().filter(|&&n| n == 1).count() >= u8::MAX as usize - 1);
+}
@@ -47,6 +47,46 @@ fn fuzzy_test(){
             assert_eq!(d, u);
         }
         
+        // range
+        for _ in 0..10 {
+            let l = rng.gen_range(0..RANGE);
+            let r = rng.gen_range(l..RANGE);
+            let mut h_keys: Vec<_> = h.keys().copied().filter(|k| (l..r).contains(k)).collect();
+            h_keys.sort();
+            assert_equal(a.range(l..r).map(|(k,_)|k), h_keys.iter().copied());
+            
+            assert_equal(a.range(l..r).rev().map(|(k,_)|k), h_keys.iter().copied().rev());
+            
+            let mut h_keys: Vec<_> = h.keys().copied().filter(|&k| k >= l).collect();
+            h_keys.sort();
+            assert_equal(a.range(l..).map(|(k,_)|k), h_keys.iter().copied());
+            assert_eq!(a.lower_bound(l).map(|(k,_)|k), h_keys.first().copied());
+            assert_eq!(a.prev_before(l).map(|(k,_)|k), h.keys().copied().filter(|&k| k < l).max());
+            
+            // Meet in the middle
+            let mut iter = a.range(l..);
+            let mut front = Vec::new();
+            let mut back = Vec::new();
+            loop {
+                let item = if rng.gen_bool(0.5) {
+                    iter.next().map(|(k,_)| front.push(k))
+                } else {
+                    iter.next_back().map(|(k,_)| back.push(k))
+                };
+                if item.is_none() { break; }
+            }
+            assert!(iter.next().is_none());
+            front.extend(back.iter().rev());
+            assert_equal(front, h_keys);
+        }
+        
+        // rev
+        {
+            let mut h_keys: Vec<_> = h.keys().copied().collect();
+            h_keys.sort();
+            assert_equal(a.iter().rev().map(|(k,_)|k), h_keys.iter().copied().rev());
+        }
+        
         // random get
         for _ in 0..COUNT {
             let v = rng.gen_range(0..RANGE);
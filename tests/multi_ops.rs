@@ -0,0 +1,93 @@
+//! multi_union / multi_intersection across all hierarchy depths.
+
+use std::collections::BTreeMap;
+use itertools::assert_equal;
+use rand::{Rng, SeedableRng};
+use hibit_tree::{config, multi_intersection, multi_map_fold, multi_union, DenseTree, HibitTree, SparseTree};
+use hibit_tree::utils::{DoubleEndedLendingIterator, LendingIterator};
+
+macro_rules! multi_ops_test {
+    ($name:ident, $tree:ty, $depth:literal) => {
+        #[test]
+        fn $name(){
+            type Tree = $tree;
+            let range = 64usize.pow($depth).min(100_000);
+            let mut rng = rand::rngs::StdRng::seed_from_u64(0x9db3dad3a2ee15bb + $depth);
+            let trees: Vec<Tree> = (0..3).map(|_|
+                (0..range.min(500))
+                    .map(|_| { let k = rng.gen_range(0..range); (k, k) })
+                    .collect()
+            ).collect();
+
+            let mut union_expected: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+            for tree in &trees {
+                for (k, v) in tree.iter() {
+                    let e = union_expected.entry(k).or_default();
+                    e.0 += v;
+                    e.1 += 1;
+                }
+            }
+
+            let union = multi_map_fold(multi_union(trees.iter()), || 0, |a, v| a + v);
+            assert_equal(union.iter(), union_expected.iter().map(|(&k, &(sum, _))| (k, sum)));
+            assert_equal(Iterator::rev(union.iter()).map(|(k, _)| k), union_expected.keys().rev().copied());
+            for k in [0, range / 2, range - 1] {
+                assert_eq!(union.get(k), union_expected.get(&k).map(|&(sum, _)| sum));
+            }
+
+            let union = multi_union(trees.iter());
+            let mut iter = union.iter();
+            let mut back: Vec<usize> = Vec::new();
+            while let Some((k, _)) = DoubleEndedLendingIterator::next_back(&mut iter) {
+                back.push(k);
+            }
+            assert_equal(back, union_expected.keys().rev().copied());
+
+            let intersection_expected: Vec<(usize, usize)> = union_expected.iter()
+                .filter(|(_, &(_, count))| count == trees.len())
+                .map(|(&k, &(sum, _))| (k, sum))
+                .collect();
+            let intersection = multi_map_fold(multi_intersection(trees.iter()), || 0, |a, v| a + v);
+            assert_equal(intersection.iter(), intersection_expected.iter().copied());
+
+            let intersection = multi_intersection(trees.iter());
+            let mut iter = intersection.iter();
+            let mut count = 0;
+            while let Some((_, values)) = LendingIterator::next(&mut iter) {
+                assert_eq!(values.count(), trees.len());
+                count += 1;
+            }
+            assert_eq!(count, intersection_expected.len());
+
+            let union = multi_union(trees.iter());
+            let intersection = multi_intersection(trees.iter());
+            assert_eq!(union.first().map(|(k, v)| (k, v.count())), union_expected.first_key_value().map(|(&k, &(_, count))| (k, count)));
+            assert_eq!(union.last().map(|(k, v)| (k, v.count())), union_expected.last_key_value().map(|(&k, &(_, count))| (k, count)));
+            for _ in 0..100 {
+                let k = rng.gen_range(0..range);
+                assert_eq!(union.lower_bound(k).map(|(k, _)| k), union_expected.range(k..).next().map(|(&k, _)| k));
+                assert_eq!(union.upper_bound(k).map(|(k, _)| k), union_expected.range(..=k).next_back().map(|(&k, _)| k));
+                assert_eq!(intersection.lower_bound(k).map(|(k, v)| (k, v.count())), intersection_expected.iter().find(|&&(i, _)| i >= k).map(|&(k, _)| (k, trees.len())));
+                assert_eq!(intersection.upper_bound(k).map(|(k, _)| k), intersection_expected.iter().rev().find(|&&(i, _)| i <= k).map(|&(k, _)| k));
+            }
+        }
+    };
+}
+
+multi_ops_test!(dense_depth_1, DenseTree<usize, 1>, 1);
+multi_ops_test!(dense_depth_2, DenseTree<usize, 2>, 2);
+multi_ops_test!(dense_depth_3, DenseTree<usize, 3>, 3);
+multi_ops_test!(dense_depth_4, DenseTree<usize, 4>, 4);
+multi_ops_test!(dense_depth_5, DenseTree<usize, 5>, 5);
+multi_ops_test!(dense_depth_6, DenseTree<usize, 6>, 6);
+multi_ops_test!(dense_depth_7, DenseTree<usize, 7>, 7);
+multi_ops_test!(dense_depth_8, DenseTree<usize, 8>, 8);
+
+multi_ops_test!(sparse_depth_1, SparseTree<config::width_64::depth_1, usize>, 1);
+multi_ops_test!(sparse_depth_2, SparseTree<config::width_64::depth_2, usize>, 2);
+multi_ops_test!(sparse_depth_3, SparseTree<config::width_64::depth_3, usize>, 3);
+multi_ops_test!(sparse_depth_4, SparseTree<config::width_64::depth_4, usize>, 4);
+multi_ops_test!(sparse_depth_5, SparseTree<config::width_64::depth_5, usize>, 5);
+multi_ops_test!(sparse_depth_6, SparseTree<config::width_64::depth_6, usize>, 6);
+multi_ops_test!(sparse_depth_7, SparseTree<config::width_64::depth_7, usize>, 7);
+multi_ops_test!(sparse_depth_8, SparseTree<config::width_64::depth_8, usize>, 8);
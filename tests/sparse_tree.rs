@@ -90,4 +90,277 @@ fn remove_test(){
     a.get_or_insert(1);
     a.get_or_insert(2);
     a.get_or_insert(400);
-}
\ No newline at end of file
+}
+
+#[test]
+fn clone_eq_test(){
+    type Tree = SparseTree<config::width_64::depth_2, Data>;
+    let mut a = Tree::default();
+    let mut b = Tree::default();
+    for i in [1, 100, 200, 3000] {
+        a.insert(i, Data(i));
+    }
+    for i in [3000, 200, 7, 1, 100] {
+        b.insert(i, Data(i));
+    }
+    b.remove(7);
+    assert_eq!(a, b);
+    assert_eq!(format!("{a:?}"), format!("{b:?}"));
+    
+    let mut c = a.clone();
+    assert_eq!(a, c);
+    c.remove(100);
+    assert_ne!(a, c);
+    assert_equal(c.iter().map(|(k, _)| k), [1, 200, 3000]);
+    assert_equal(a.iter().map(|(k, _)| k), [1, 100, 200, 3000]);
+}
+
+#[test]
+fn len_clear_contains_test(){
+    type Tree = SparseTree<config::width_64::depth_2, Data>;
+    let mut a = Tree::default();
+    assert!(a.is_empty());
+    assert!(HibitTree::is_empty(&a));
+    
+    for i in [1, 100, 200, 3000] {
+        a.insert(i, Data(i));
+    }
+    assert_eq!(a.len(), 4);
+    assert!(!HibitTree::is_empty(&a));
+    assert!(a.contains(200));
+    assert!(!a.contains(201));
+    
+    a.clear();
+    assert_eq!(a.len(), 0);
+    assert!(HibitTree::is_empty(&a));
+    assert!(!a.contains(200));
+    assert_eq!(a.iter().count(), 0);
+    
+    a.insert(200, Data(200));
+    assert_equal(a.iter(), [(200, &Data(200))]);
+}
+
+#[test]
+fn from_iter_test(){
+    type Tree = SparseTree<config::width_64::depth_3, Data>;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0xe15bb9db3dee3a0f);
+    let mut keys: Vec<usize> = (0..10_000).map(|_| rng.gen_range(0..260_000)).collect();
+    keys.sort();
+    keys.dedup();
+    
+    let sorted = Tree::from_sorted_iter(keys.iter().map(|&k| (k, Data(k))));
+    let collected: Tree = keys.iter().rev().map(|&k| (k, Data(k))).collect();
+    assert_eq!(sorted.len(), keys.len());
+    assert_eq!(sorted, collected);
+    assert_equal(sorted.iter().map(|(k, _)| k), keys.iter().copied());
+    
+    // remove() relies on last level block indices.
+    let mut sorted = sorted;
+    for &k in keys.iter().step_by(2) {
+        assert_eq!(sorted.remove(k), Some(Data(k)));
+    }
+    assert_equal(sorted.iter().map(|(k, _)| k), keys.iter().copied().skip(1).step_by(2));
+}
+
+#[test]
+#[should_panic]
+fn from_sorted_iter_unsorted_test(){
+    type Tree = SparseTree<config::width_64::depth_2, Data>;
+    Tree::from_sorted_iter([(1, Data(1)), (1, Data(1))]);
+}
+
+#[test]
+fn entry_test(){
+    use hibit_tree::sparse::Entry;
+    type Tree = SparseTree<config::width_64::depth_3, usize>;
+    let mut a = Tree::default();
+    
+    *a.entry(15).or_insert(0) += 1;
+    *a.entry(15).or_insert(0) += 1;
+    *a.entry(70_000).or_default() += 5;
+    a.entry(16).and_modify(|v| *v = 100).or_insert_with(|| 16);
+    a.entry(15).and_modify(|v| *v += 10).or_insert_with_key(|k| k);
+    assert_equal(a.iter(), [(15, &12), (16, &16), (70_000, &5)]);
+    
+    match a.entry(16) {
+        Entry::Occupied(mut e) => {
+            assert_eq!(e.insert(17), 16);
+            assert_eq!(e.remove(), 17);
+        }
+        Entry::Vacant(_) => unreachable!()
+    }
+    match a.entry(15) {
+        Entry::Occupied(e) => assert_eq!(e.remove(), 12),
+        Entry::Vacant(_) => unreachable!()
+    }
+    match a.entry(70_001) {
+        Entry::Vacant(e) => { e.insert(1); },
+        Entry::Occupied(_) => unreachable!()
+    }
+    assert_equal(a.iter(), [(70_000, &5), (70_001, &1)]);
+    assert_eq!(a.remove(70_000), Some(5));
+    assert_equal(a.iter(), [(70_001, &1)]);
+}
+
+#[test]
+fn retain_test(){
+    type Tree = SparseTree<config::width_64::depth_3, Data>;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0x3a2ee15bb9db3dad);
+    let mut keys: Vec<usize> = (0..4000).map(|_| rng.gen_range(0..262_144)).collect();
+    keys.sort();
+    keys.dedup();
+    
+    let mut a: Tree = keys.iter().rev().map(|&k| (k, Data(k))).collect();
+    a.retain(|k, v| { v.0 += 1; k % 3 != 0 });
+    let expected: Vec<_> = keys.iter().copied().filter(|k| k % 3 != 0).collect();
+    assert_equal(a.iter().map(|(k, v)| (k, v.0)), expected.iter().map(|&k| (k, k + 1)));
+    assert_eq!(a.len(), expected.len());
+    
+    let extracted: Vec<_> = a.extract_if(|k, _| k < 100_000).collect();
+    assert_equal(extracted, expected.iter().copied().filter(|&k| k < 100_000).map(|k| (k, Data(k + 1))));
+    let expected: Vec<_> = expected.into_iter().filter(|&k| k >= 100_000).collect();
+    assert_equal(a.iter().map(|(k, _)| k), expected.iter().copied());
+    
+    // remove() relies on last level block indices.
+    for &k in expected.iter().step_by(2) {
+        assert_eq!(a.remove(k), Some(Data(k + 1)));
+    }
+    a.insert(5, Data(5));
+    assert_equal(a.iter().map(|(k, _)| k), [5].into_iter().chain(expected.iter().copied().skip(1).step_by(2)));
+    
+    a.retain(|_, _| false);
+    assert!(a.is_empty());
+    a.insert(5, Data(5));
+    assert_equal(a.iter(), [(5, &Data(5))]);
+    
+    // Default placeholder must survive.
+    type DefaultTree = SparseTree<config::width_64::depth_2, String, hibit_tree::ReqDefault>;
+    let mut b: DefaultTree = (0..100).map(|k| (k, k.to_string())).collect();
+    let extracted: Vec<_> = b.extract_if(|k, _| k >= 10).map(|(_, v)| v).collect();
+    assert_eq!(extracted.len(), 90);
+    assert_eq!(b.get_or_default(50), "");
+    assert_eq!(b.get_or_default(5), "5");
+    
+    // Panicking `pred` keeps not yet visited elements.
+    let mut c: Tree = (0..100).map(|k| (k, Data(k))).collect();
+    let r = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        c.extract_if(|k, _| if k == 50 { panic!() } else { k % 2 == 0 }).count()
+    }));
+    assert!(r.is_err());
+    assert_equal(c.iter().map(|(k, _)| k), (0..100).filter(|&k| k % 2 != 0 || k >= 50));
+    assert_eq!(c.len(), 75);
+    assert_eq!(c.remove(51), Some(Data(51)));
+}
+
+#[test]
+fn set_ops_test(){
+    type Tree = SparseTree<config::width_64::depth_3, Data>;
+    let a: Tree = (0..5000).map(|k| (k, Data(k))).collect();
+    let by2: Tree = (0..5000).step_by(2).map(|k| (k, Data(k))).collect();
+    let by3: Tree = (0..5000).step_by(3).map(|k| (k, Data(k))).collect();
+
+    let mut t = a.clone();
+    t.intersect_with(&hibit_tree::intersection(&by2, &by3));
+    assert_equal(t.iter().map(|(k, v)| (k, v.0)), (0..5000).step_by(6).map(|k| (k, k)));
+
+    let mut t = a.clone();
+    t.difference_with(&by2);
+    assert_equal(t.iter().map(|(k, _)| k), (1..5000).step_by(2));
+    // remove() relies on last level block indices.
+    assert_eq!(t.remove(1), Some(Data(1)));
+    t.difference_with(&a);
+    assert!(t.is_empty());
+
+    let mut t = by2.clone();
+    t.union_with(&by3, |v: Option<Data>, d: &Data| Data(v.map_or(0, |v| v.0) + d.0));
+    assert_equal(
+        t.iter().map(|(k, v)| (k, v.0)),
+        (0..5000).filter(|k| k % 2 == 0 || k % 3 == 0).map(|k| (k, if k % 6 == 0 { 2 * k } else { k }))
+    );
+}
+
+#[test]
+#[cfg(feature = "simd")]
+fn wide_rev_range_test(){
+    type Tree = SparseTree<config::width_256::depth_2, usize>;
+    let keys = [0, 3, 63, 64, 200, 255, 256, 1000, 20_000, 65_535];
+    let a: Tree = keys.iter().map(|&k| (k, k)).collect();
+    
+    assert_equal(a.iter().rev().map(|(k, _)| k), keys.iter().copied().rev());
+    assert_equal(a.range(64..=20_000).rev().map(|(k, _)| k), [20_000, 1000, 256, 255, 200, 64]);
+    assert_equal(a.range(4..256).map(|(k, _)| k), [63, 64, 200, 255]);
+    
+    let mut iter = a.range(1..65_535);
+    assert_eq!(iter.next_back().map(|(k, _)| k), Some(20_000));
+    assert_eq!(Iterator::next(&mut iter).map(|(k, _)| k), Some(3));
+    assert_equal(iter.rev().map(|(k, _)| k), [1000, 256, 255, 200, 64, 63]);
+}
+
+#[test]
+fn pop_test(){
+    type Tree = SparseTree<config::width_64::depth_3, usize>;
+    let mut a: Tree = [(5, 5), (70_000, 70_000), (100, 100), (3, 3)].into_iter().collect();
+    assert_eq!(a.first_key_value(), Some((3, &3)));
+    assert_eq!(a.last_key_value(), Some((70_000, &70_000)));
+    
+    assert_eq!(a.pop_first(), Some((3, 3)));
+    assert_eq!(a.pop_last(), Some((70_000, 70_000)));
+    assert_eq!(a.pop_last(), Some((100, 100)));
+    assert_equal(a.iter(), [(5, &5)]);
+    assert_eq!(a.pop_first(), Some((5, 5)));
+    assert_eq!(a.pop_first(), None);
+    assert!(a.is_empty());
+    
+    // Still usable after pruning.
+    a.insert(200_000, 1);
+    assert_eq!(a.pop_last(), Some((200_000, 1)));
+    assert_eq!(a.last_key_value(), None);
+}
+
+#[test]
+fn capacity_test(){
+    type Tree = SparseTree<config::width_64::depth_3, usize>;
+    let mut a = Tree::with_capacity(100);
+    assert!(a.capacity() >= 100);
+    a.reserve(1000);
+    assert!(a.capacity() >= 1000);
+    
+    a.extend((0..100).map(|k| (k * 7, k)));
+    a.shrink_to_fit();
+    assert_eq!(a.capacity(), 100);
+    assert_equal(Iterator::map(a.iter(), |(k, v)| (k, *v)), (0..100).map(|k| (k * 7, k)));
+    
+    a.clear();
+    a.shrink_to_fit();
+    assert_eq!(a.capacity(), 0);
+    a.insert(5, 5);
+    assert_equal(a.iter(), [(5, &5)]);
+}
+
+#[test]
+fn memory_stats_test(){
+    use std::mem::size_of;
+    type Tree = SparseTree<config::width_64::depth_3, usize>;
+    
+    let mut a = Tree::default();
+    let stats = a.memory_stats();
+    assert_equal(stats.levels.iter().map(|l| l.nodes), [1, 0, 0]);
+    
+    a.extend([(0, 0), (1, 1), (64, 64), (4096, 4096)]);
+    let stats = a.memory_stats();
+    assert_equal(stats.levels.iter().map(|l| l.nodes), [1, 2, 3]);
+    assert_equal(stats.levels.iter().map(|l| l.used_slots), [2, 3, 4]);
+    assert_equal(stats.levels.iter().map(|l| l.allocated_slots), [64, 128, 192]);
+    assert_equal(stats.levels.iter().map(|l| l.mask_bytes), [8, 16, 24]);
+    assert_eq!(stats.data_bytes, (a.capacity() + 1) * size_of::<usize>());
+    assert!(stats.sentinel_bytes > 0);
+    
+    // Emptied blocks become free.
+    let node_bytes = stats.node_bytes();
+    let free_bytes = stats.free_bytes;
+    a.remove(4096);
+    let stats = a.memory_stats();
+    assert_equal(stats.levels.iter().map(|l| l.nodes), [1, 1, 2]);
+    assert_eq!(stats.node_bytes() + stats.free_bytes, node_bytes + free_bytes);
+    assert!(stats.free_bytes > free_bytes);
+}